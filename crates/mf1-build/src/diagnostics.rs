@@ -0,0 +1,26 @@
+//! Warnings about a catalog, which don't stop its code from being generated.
+//!
+//! From `load_locales!` they are printed to stderr. From a build script they
+//! are collected, and reported with `cargo:warning`.
+
+use std::cell::RefCell;
+
+thread_local! {
+    static WARNINGS: RefCell<Option<Vec<String>>> = const { RefCell::new(None) };
+}
+
+/// Reports a warning.
+pub(crate) fn warn(message: String) {
+    WARNINGS.with(|warnings| match &mut *warnings.borrow_mut() {
+        Some(warnings) => warnings.push(message),
+        None => eprintln!("{}", message),
+    })
+}
+
+/// Runs `f`, collecting the warnings it reports instead of printing them.
+pub(crate) fn collect<R>(f: impl FnOnce() -> R) -> (R, Vec<String>) {
+    let previous = WARNINGS.with(|warnings| warnings.replace(Some(Vec::new())));
+    let result = f();
+    let collected = WARNINGS.with(|warnings| warnings.replace(previous));
+    (result, collected.unwrap_or_default())
+}
@@ -0,0 +1,132 @@
+//! Names of locales in their own language, for `Locale::display_name`.
+//!
+//! This covers the languages most apps are translated into. Other locales can
+//! be named with `display_names` in `[package.metadata.mf1]`, and otherwise
+//! use their tag.
+
+use icu_locid::LanguageIdentifier;
+
+/// Autonyms of locales with a script or region, which are checked first.
+const QUALIFIED: &[(&str, &str)] = &[
+    ("en-GB", "English (United Kingdom)"),
+    ("en-US", "English (United States)"),
+    ("es-419", "Español (Latinoamérica)"),
+    ("es-MX", "Español (México)"),
+    ("fr-CA", "Français (Canada)"),
+    ("pt-BR", "Português (Brasil)"),
+    ("pt-PT", "Português (Portugal)"),
+    ("sr-Latn", "Srpski"),
+    ("zh-Hans", "简体中文"),
+    ("zh-Hant", "繁體中文"),
+    ("zh-CN", "简体中文"),
+    ("zh-HK", "繁體中文（香港）"),
+    ("zh-TW", "繁體中文"),
+];
+
+/// Autonyms of languages.
+const LANGUAGES: &[(&str, &str)] = &[
+    ("af", "Afrikaans"),
+    ("am", "አማርኛ"),
+    ("ar", "العربية"),
+    ("az", "Azərbaycan"),
+    ("be", "Беларуская"),
+    ("bg", "Български"),
+    ("bn", "বাংলা"),
+    ("bs", "Bosanski"),
+    ("ca", "Català"),
+    ("cs", "Čeština"),
+    ("cy", "Cymraeg"),
+    ("da", "Dansk"),
+    ("de", "Deutsch"),
+    ("el", "Ελληνικά"),
+    ("en", "English"),
+    ("eo", "Esperanto"),
+    ("es", "Español"),
+    ("et", "Eesti"),
+    ("eu", "Euskara"),
+    ("fa", "فارسی"),
+    ("fi", "Suomi"),
+    ("fil", "Filipino"),
+    ("fr", "Français"),
+    ("ga", "Gaeilge"),
+    ("gl", "Galego"),
+    ("gu", "ગુજરાતી"),
+    ("he", "עברית"),
+    ("hi", "हिन्दी"),
+    ("hr", "Hrvatski"),
+    ("hu", "Magyar"),
+    ("hy", "Հայերեն"),
+    ("id", "Indonesia"),
+    ("is", "Íslenska"),
+    ("it", "Italiano"),
+    ("ja", "日本語"),
+    ("ka", "ქართული"),
+    ("kk", "Қазақ тілі"),
+    ("km", "ខ្មែរ"),
+    ("kn", "ಕನ್ನಡ"),
+    ("ko", "한국어"),
+    ("lt", "Lietuvių"),
+    ("lv", "Latviešu"),
+    ("mk", "Македонски"),
+    ("ml", "മലയാളം"),
+    ("mn", "Монгол"),
+    ("mr", "मराठी"),
+    ("ms", "Melayu"),
+    ("my", "မြန်မာ"),
+    ("nb", "Norsk bokmål"),
+    ("ne", "नेपाली"),
+    ("nl", "Nederlands"),
+    ("nn", "Norsk nynorsk"),
+    ("no", "Norsk"),
+    ("pa", "ਪੰਜਾਬੀ"),
+    ("pl", "Polski"),
+    ("pt", "Português"),
+    ("ro", "Română"),
+    ("ru", "Русский"),
+    ("si", "සිංහල"),
+    ("sk", "Slovenčina"),
+    ("sl", "Slovenščina"),
+    ("sq", "Shqip"),
+    ("sr", "Српски"),
+    ("sv", "Svenska"),
+    ("sw", "Kiswahili"),
+    ("ta", "தமிழ்"),
+    ("te", "తెలుగు"),
+    ("th", "ไทย"),
+    ("tr", "Türkçe"),
+    ("uk", "Українська"),
+    ("ur", "اردو"),
+    ("uz", "Oʻzbek"),
+    ("vi", "Tiếng Việt"),
+    ("zh", "中文"),
+    ("zu", "isiZulu"),
+];
+
+/// Returns the name of a locale in its own language, if it is known.
+pub fn autonym(langid: &LanguageIdentifier) -> Option<&'static str> {
+    let lookup = |table: &[(&str, &'static str)], tag: &str| {
+        table.iter().find(|(t, _)| *t == tag).map(|(_, name)| *name)
+    };
+    let language = langid.language.as_str();
+    let script = langid.script.map(|s| format!("{}-{}", language, s));
+    let region = langid.region.map(|r| format!("{}-{}", language, r));
+    script
+        .iter()
+        .chain(region.iter())
+        .find_map(|tag| lookup(QUALIFIED, tag))
+        .or_else(|| lookup(LANGUAGES, language))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn qualified_names_first() {
+        let name = |tag: &str| autonym(&tag.parse().unwrap());
+        assert_eq!(name("pt-BR"), Some("Português (Brasil)"));
+        assert_eq!(name("pt-AO"), Some("Português"));
+        assert_eq!(name("zh-Hant-HK"), Some("繁體中文"));
+        assert_eq!(name("tlh"), None);
+    }
+}
@@ -0,0 +1,170 @@
+//! Mangling of locale file keys into Rust identifiers.
+//!
+//! Keys in locale files can be any string, but the fields generated for them
+//! must be valid identifiers. A key is mangled as follows:
+//!
+//! 1. Every character that can't appear in an identifier, such as `-`, `.`
+//!    or a space, is replaced with `_`, so `sign-in` becomes `sign_in`.
+//! 2. A key that doesn't start with a letter or `_`, or that is only `_`, is
+//!    prefixed with `_`, so `404` becomes `_404`.
+//! 3. Keywords become raw identifiers, so `type` becomes `r#type`. `self`,
+//!    `Self`, `super` and `crate` can't be raw identifiers, and get a `_`
+//!    suffix instead.
+//!
+//! `load_locales!` reports an error if two keys mangle to the same identifier.
+//! Macros taking a key path mangle each segment the same way, so keys can be
+//! written with their original spelling.
+
+use proc_macro2::{Span, TokenStream};
+use syn::{ext::IdentExt, parse::ParseStream, token, Ident, LitInt, LitStr};
+
+const KEYWORDS: &[&str] = &[
+    "abstract", "as", "async", "await", "become", "box", "break", "const", "continue", "do", "dyn",
+    "else", "enum", "extern", "false", "final", "fn", "for", "gen", "if", "impl", "in", "let",
+    "loop", "macro", "match", "mod", "move", "mut", "override", "priv", "pub", "ref", "return",
+    "static", "struct", "trait", "true", "try", "type", "typeof", "unsafe", "unsized", "use",
+    "virtual", "where", "while", "yield",
+];
+
+const NON_RAW_KEYWORDS: &[&str] = &["crate", "self", "Self", "super"];
+
+/// Mangles a key into the identifier used for it in generated code.
+pub fn mangle(key: &str) -> Ident {
+    let mut name: String = key
+        .chars()
+        .map(|c| {
+            if unicode_ident::is_xid_continue(c) {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    if name == "_" || !name.starts_with(|c| unicode_ident::is_xid_start(c) || c == '_') {
+        name.insert(0, '_');
+    }
+    if KEYWORDS.contains(&name.as_str()) {
+        Ident::new_raw(&name, Span::call_site())
+    } else {
+        if NON_RAW_KEYWORDS.contains(&name.as_str()) {
+            name.push('_');
+        }
+        Ident::new(&name, Span::call_site())
+    }
+}
+
+/// A segment of a key path in a macro invocation, in its original spelling.
+///
+/// Segments can be identifiers (including keywords and raw identifiers),
+/// integers, or string literals for keys that are not valid identifiers.
+pub struct KeySegment {
+    pub key: String,
+    pub span: Span,
+}
+
+impl KeySegment {
+    pub fn ident(&self) -> Ident {
+        let mut ident = mangle(&self.key);
+        ident.set_span(self.span);
+        ident
+    }
+}
+
+impl syn::parse::Parse for KeySegment {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let lookahead = input.lookahead1();
+        if lookahead.peek(LitStr) {
+            let lit: LitStr = input.parse()?;
+            Ok(KeySegment {
+                key: lit.value(),
+                span: lit.span(),
+            })
+        } else if lookahead.peek(LitInt) {
+            let lit: LitInt = input.parse()?;
+            Ok(KeySegment {
+                key: lit.to_string(),
+                span: lit.span(),
+            })
+        } else if lookahead.peek(Ident::peek_any) {
+            let ident = Ident::parse_any(input)?;
+            Ok(KeySegment {
+                key: ident.unraw().to_string(),
+                span: ident.span(),
+            })
+        } else {
+            Err(lookahead.error())
+        }
+    }
+}
+
+impl quote::ToTokens for KeySegment {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        self.ident().to_tokens(tokens)
+    }
+}
+
+/// Parses a `.`-separated key path, such as `nested."sign-in"`.
+pub fn parse_key_path(input: ParseStream) -> syn::Result<Vec<KeySegment>> {
+    let mut keys = vec![input.parse()?];
+    while input.peek(token::Dot) {
+        input.parse::<token::Dot>()?;
+        keys.push(input.parse()?);
+    }
+    Ok(keys)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mangled(key: &str) -> String {
+        mangle(key).to_string()
+    }
+
+    #[test]
+    fn mangled_keys() {
+        assert_eq!(mangled("message"), "message");
+        assert_eq!(mangled("sign-in"), "sign_in");
+        assert_eq!(mangled("a.b c"), "a_b_c");
+        assert_eq!(mangled("héllo wörld"), "héllo_wörld");
+        assert_eq!(mangled("emoji 🦀"), "emoji__");
+        assert_eq!(mangled("_private"), "_private");
+    }
+
+    #[test]
+    fn leading_digits_and_underscores() {
+        assert_eq!(mangled("404"), "_404");
+        assert_eq!(mangled("2fa"), "_2fa");
+        assert_eq!(mangled("-"), "__");
+        assert_eq!(mangled("_"), "__");
+    }
+
+    #[test]
+    fn keywords() {
+        assert_eq!(mangled("type"), "r#type");
+        assert_eq!(mangled("match"), "r#match");
+        assert_eq!(mangled("async"), "r#async");
+        assert_eq!(mangled("Type"), "Type");
+        for key in ["self", "Self", "super", "crate"] {
+            assert_eq!(mangled(key), format!("{}_", key));
+        }
+    }
+
+    #[test]
+    fn key_segments() {
+        let segments = syn::parse::Parser::parse_str(
+            parse_key_path,
+            r#"nested."sign-in".404.r#type.self.café"#,
+        )
+        .unwrap();
+        let keys: Vec<_> = segments.iter().map(|s| s.key.as_str()).collect();
+        assert_eq!(keys, ["nested", "sign-in", "404", "type", "self", "café"]);
+        let idents: Vec<_> = segments.iter().map(|s| s.ident().to_string()).collect();
+        assert_eq!(
+            idents,
+            ["nested", "sign_in", "_404", "r#type", "self_", "café"]
+        );
+        assert!(syn::parse::Parser::parse_str(parse_key_path, "nested.").is_err());
+        assert!(syn::parse::Parser::parse_str(parse_key_path, "'a'").is_err());
+    }
+}
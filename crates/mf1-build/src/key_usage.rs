@@ -0,0 +1,306 @@
+//! Finds the keys a crate uses, by scanning its sources for the message
+//! macros of `mf1`, to report keys which are never used and keys which don't
+//! exist.
+//!
+//! Macro calls are found anywhere in a file, including in the arguments of
+//! other macros such as `println!`, as long as those are expressions. Macros
+//! imported under another name, as with `use mf1::t_l_string as t;`, are
+//! found by that name anywhere in the file. Keys
+//! used through the generated structs, such as `en.nested.keys`, aren't
+//! found, so those keys are reported as unused.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::io;
+use std::path::{Path, PathBuf};
+
+use syn::punctuated::Punctuated;
+use syn::visit::Visit;
+use syn::{token, Expr};
+
+use crate::ident::mangle;
+use crate::load_locales::{Keys, StringItem};
+use crate::macro_input::{InterpolatedValue, ParsedInput, WriteInput};
+use crate::{Error, StringSet};
+
+/// A key given to a message macro.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyUsage {
+    /// The segments of the key path, as they are written.
+    pub key: Vec<String>,
+    pub file: PathBuf,
+    /// The line of the key, starting at 1.
+    pub line: usize,
+    /// The column of the key in characters, starting at 1.
+    pub column: usize,
+}
+
+impl KeyUsage {
+    /// The dotted path of the key, such as `nested.keys`.
+    pub fn path(&self) -> String {
+        self.key.join(".")
+    }
+}
+
+/// The keys of the base locale which are never used, and the uses of keys
+/// which aren't in the base locale.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct KeyReport {
+    /// The dotted paths of the unused messages.
+    pub unused: Vec<String>,
+    pub undefined: Vec<KeyUsage>,
+}
+
+/// The message macros, by name.
+const MESSAGE_MACROS: &[&str] = &[
+    "t_l_string",
+    "t_l_display",
+    "t_l_write",
+    "t_string",
+    "t_display",
+    "t_write",
+];
+
+/// Finds the message macros a file imports under another name, such as
+/// `use mf1::t_l_string as t;`.
+#[derive(Default)]
+struct RenameVisitor {
+    renames: BTreeMap<String, &'static str>,
+}
+
+impl<'ast> Visit<'ast> for RenameVisitor {
+    fn visit_use_rename(&mut self, rename: &'ast syn::UseRename) {
+        let name = rename.ident.to_string();
+        if let Some(name) = MESSAGE_MACROS.iter().find(|m| **m == name) {
+            self.renames.insert(rename.rename.to_string(), name);
+        }
+    }
+}
+
+struct MacroVisitor<'f> {
+    file: &'f Path,
+    /// The message macros imported under another name, by that name.
+    renames: BTreeMap<String, &'static str>,
+    usages: Vec<KeyUsage>,
+}
+
+impl MacroVisitor<'_> {
+    fn visit_input(&mut self, input: &ParsedInput) {
+        let start = input.keys[0].span.start();
+        self.usages.push(KeyUsage {
+            key: input
+                .keys
+                .iter()
+                .map(|segment| segment.key.clone())
+                .collect(),
+            file: self.file.to_owned(),
+            line: start.line,
+            column: start.column + 1,
+        });
+        self.visit_expr(&input.context);
+        for interpolation in input.interpolations.iter().flatten() {
+            if let InterpolatedValue::AssignedVar { value, .. } = interpolation {
+                self.visit_expr(value);
+            }
+        }
+    }
+}
+
+impl<'ast> Visit<'ast> for MacroVisitor<'_> {
+    fn visit_macro(&mut self, mac: &'ast syn::Macro) {
+        let Some(name) = mac.path.segments.last().map(|s| s.ident.to_string()) else {
+            return;
+        };
+        let name = match self.renames.get(&name) {
+            Some(name) => *name,
+            None => name.as_str(),
+        };
+        // A message macro which doesn't parse is reported by the compiler.
+        match name {
+            "t_l_string" | "t_l_display" => {
+                if let Ok(input) = mac.parse_body::<ParsedInput>() {
+                    self.visit_input(&input);
+                }
+            }
+            "t_string" | "t_display" => {
+                if let Ok(input) = mac.parse_body_with(ParsedInput::parse_ambient) {
+                    self.visit_input(&input);
+                }
+            }
+            "t_l_write" | "t_write" => {
+                let ambient = name == "t_write";
+                if let Ok(write) = mac.parse_body_with(|input: syn::parse::ParseStream| {
+                    WriteInput::parse(input, ambient)
+                }) {
+                    self.visit_expr(&write.writer);
+                    self.visit_input(&write.input);
+                }
+            }
+            _ => {
+                let args = mac.parse_body_with(Punctuated::<Expr, token::Comma>::parse_terminated);
+                for arg in args.iter().flatten() {
+                    self.visit_expr(arg);
+                }
+            }
+        }
+    }
+}
+
+/// Finds the keys used in the source of a Rust file.
+pub fn scan_source(file: &Path, src: &str) -> syn::Result<Vec<KeyUsage>> {
+    // In a proc macro, tokens parsed from a string all have the span of the
+    // macro call, so the file is parsed with the fallback implementation to
+    // get the lines of the keys. No tokens are kept once it is parsed.
+    proc_macro2::fallback::force();
+    let usages = syn::parse_file(src).map(|ast| {
+        let mut renames = RenameVisitor::default();
+        renames.visit_file(&ast);
+        let mut visitor = MacroVisitor {
+            file,
+            renames: renames.renames,
+            usages: Vec::new(),
+        };
+        visitor.visit_file(&ast);
+        visitor.usages
+    });
+    proc_macro2::fallback::unforce();
+    usages
+}
+
+/// Finds the keys used in the `.rs` files in a directory and its
+/// subdirectories.
+pub fn scan_dir(dir: &Path) -> Result<Vec<KeyUsage>, Error> {
+    let mut files = Vec::new();
+    rust_files(dir, &mut files).map_err(|err| Error::ReadSource {
+        path: dir.to_owned(),
+        err,
+    })?;
+    let mut usages = Vec::new();
+    for path in files {
+        let src = std::fs::read_to_string(&path).map_err(|err| Error::ReadSource {
+            path: path.clone(),
+            err,
+        })?;
+        usages.extend(scan_source(&path, &src).map_err(|err| Error::ParseSource {
+            path: path.clone(),
+            err,
+        })?);
+    }
+    Ok(usages)
+}
+
+/// Finds the `.rs` files in a directory, in sorted order.
+fn rust_files(dir: &Path, files: &mut Vec<PathBuf>) -> io::Result<()> {
+    let mut entries = std::fs::read_dir(dir)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<io::Result<Vec<_>>>()?;
+    entries.sort();
+    for path in entries {
+        if path.is_dir() {
+            rust_files(&path, files)?;
+        } else if path.extension().is_some_and(|ext| ext == "rs") {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// The identifier a key path is generated as, which is how the macros find
+/// the key whatever spelling it is written with.
+fn mangled_path<'k>(segments: impl IntoIterator<Item = &'k str>) -> String {
+    segments
+        .into_iter()
+        .map(|segment| mangle(segment).to_string())
+        .collect::<Vec<_>>()
+        .join(".")
+}
+
+/// Finds the messages in a map of keys, by their mangled path.
+fn messages(prefix: &[&str], keys: &Keys, out: &mut BTreeMap<String, String>) {
+    for (k, v) in keys {
+        let mut path = prefix.to_vec();
+        path.push(k);
+        match v {
+            StringItem::String(_) => {
+                out.insert(mangled_path(path.iter().copied()), path.join("."));
+            }
+            StringItem::Subkey(keys) => messages(&path, keys, out),
+            StringItem::Metadata(_) => {}
+        }
+    }
+}
+
+/// Compares the keys used by a crate with the messages of its base locale.
+pub fn compare(base: &StringSet, usages: Vec<KeyUsage>) -> KeyReport {
+    let mut defined = BTreeMap::new();
+    messages(&[], &base.keys, &mut defined);
+    let mut used = BTreeSet::new();
+    let mut undefined = Vec::new();
+    for usage in usages {
+        let path = mangled_path(usage.key.iter().map(String::as_str));
+        if defined.contains_key(&path) {
+            used.insert(path);
+        } else {
+            undefined.push(usage);
+        }
+    }
+    let unused = defined
+        .into_iter()
+        .filter(|(path, _)| !used.contains(path))
+        .map(|(_, key)| key)
+        .collect();
+    KeyReport { unused, undefined }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::load_locales::deserialize_keys;
+
+    #[test]
+    fn unused_and_undefined_keys() {
+        let src = r##"
+            fn main() {
+                let name = "Ferris";
+                println!("{}", t_l_string!(Locale::en, message));
+                let s = mf1::t_l_string!(locale, nested."sign-in", name);
+                t_l_write!(out, locale, nested.sign_in, count = t_string!(r#type)).unwrap();
+                assert_eq!(t_display!(missing.key).to_string(), "");
+                t!(Locale::es_419, "sign-out");
+                td!(Locale::es, other.missing);
+            }
+            use mf1::{t_l_display as td, t_l_string as t};
+        "##;
+        let usages = scan_source(Path::new("src/main.rs"), src).unwrap();
+        let keys: Vec<_> = usages.iter().map(KeyUsage::path).collect();
+        assert_eq!(
+            keys,
+            [
+                "message",
+                "nested.sign-in",
+                "nested.sign_in",
+                "type",
+                "missing.key",
+                "sign-out",
+                "other.missing"
+            ]
+        );
+        assert_eq!((usages[0].line, usages[0].column), (4, 56));
+
+        let keys = deserialize_keys(&mut serde_json::Deserializer::from_str(
+            r#"{"message": "M", "unused": "U", "type": "T", "@type": {},
+                "sign-out": "O", "nested": {"sign-in": "S", "sign-out": "O"}}"#,
+        ))
+        .unwrap();
+        let base = StringSet {
+            name: "en",
+            keys,
+            fallback: None,
+            feature: None,
+        };
+        let report = compare(&base, usages);
+        assert_eq!(report.unused, ["nested.sign-out", "unused"]);
+        let undefined: Vec<_> = report.undefined.iter().map(KeyUsage::path).collect();
+        assert_eq!(undefined, ["missing.key", "other.missing"]);
+        assert_eq!(report.undefined[0].line, 7);
+    }
+}
@@ -0,0 +1,126 @@
+//! Generates the code for the messages of an mf1 catalog.
+//!
+//! This is what the `load_locales!` macro of `mf1` expands to. From a build
+//! script, [`Builder::build`] writes the same code into `OUT_DIR` instead,
+//! where it can be read, and where it is only regenerated when the catalog
+//! changes:
+//!
+//! ```rust,ignore
+//! // build.rs
+//! fn main() {
+//!     mf1_build::Builder::new().build().unwrap();
+//! }
+//!
+//! // src/main.rs
+//! include!(concat!(env!("OUT_DIR"), "/locales.rs"));
+//! ```
+//!
+//! The catalog is configured in `[package.metadata.mf1]` as for the macro.
+
+use std::path::PathBuf;
+
+use proc_macro2::TokenStream;
+
+mod diagnostics;
+mod display_names;
+pub mod ident;
+pub mod key_usage;
+mod load_locales;
+pub mod macro_input;
+
+pub use load_locales::{
+    parse_args, ArgMetadata, CatalogConfig, Codegen, ConfigFile, Error, LocaleEntry, Metadata,
+    StringItem, StringSet,
+};
+
+/// Generates the code for a catalog.
+#[derive(Debug, Clone, Default)]
+pub struct Builder {
+    pub(crate) manifest_dir: Option<PathBuf>,
+    pub(crate) codegen: Option<Codegen>,
+    pub(crate) ambient_locale: bool,
+    pub(crate) serde: bool,
+    pub(crate) check_keys: Option<bool>,
+    out_file: Option<String>,
+}
+
+impl Builder {
+    pub fn new() -> Self {
+        Builder::default()
+    }
+
+    /// The directory of the `Cargo.toml` with the config of the catalog.
+    /// Defaults to `CARGO_MANIFEST_DIR`.
+    pub fn manifest_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.manifest_dir = Some(dir.into());
+        self
+    }
+
+    /// Overrides the `codegen` of the config.
+    pub fn codegen(mut self, codegen: Codegen) -> Self {
+        self.codegen = Some(codegen);
+        self
+    }
+
+    /// Generates the thread-local current locale used by `t_string!` and
+    /// friends, for crates which enable the `ambient-locale` feature of `mf1`.
+    pub fn ambient_locale(mut self, enabled: bool) -> Self {
+        self.ambient_locale = enabled;
+        self
+    }
+
+    /// Generates `Serialize` and `Deserialize` impls for `Locale`, for crates
+    /// which enable the `serde` feature of `mf1`.
+    pub fn serde(mut self, enabled: bool) -> Self {
+        self.serde = enabled;
+        self
+    }
+
+    /// Overrides the `check_keys` of the config: whether to warn about keys
+    /// which no message macro in `src` uses, and about message macros using
+    /// keys which don't exist.
+    ///
+    /// With [`Builder::build`], this makes cargo rerun the build script
+    /// whenever a file in `src` changes.
+    pub fn check_keys(mut self, enabled: bool) -> Self {
+        self.check_keys = Some(enabled);
+        self
+    }
+
+    /// The name of the file written into `OUT_DIR` by [`Builder::build`].
+    /// Defaults to `locales.rs`.
+    pub fn out_file(mut self, name: impl Into<String>) -> Self {
+        self.out_file = Some(name.into());
+        self
+    }
+
+    /// Generates the code for the catalog. Warnings are printed to stderr.
+    pub fn generate(&self) -> Result<TokenStream, Error> {
+        load_locales::load_locales(self, &mut Vec::new())
+    }
+
+    /// Writes the formatted code for the catalog into `OUT_DIR`, and returns
+    /// its path.
+    ///
+    /// This is meant to be called from a build script: it tells cargo to
+    /// rerun the script when the catalog or its config changes, and reports
+    /// warnings with `cargo:warning`.
+    pub fn build(&self) -> Result<PathBuf, Error> {
+        let mut sources = Vec::new();
+        let (tokens, warnings) =
+            diagnostics::collect(|| load_locales::load_locales(self, &mut sources));
+        for source in &sources {
+            println!("cargo:rerun-if-changed={}", source.display());
+        }
+        for warning in &warnings {
+            println!("cargo:warning={}", warning);
+        }
+        let file = syn::parse2::<syn::File>(tokens?).map_err(Error::FormatOutput)?;
+        let out_dir: PathBuf = std::env::var("OUT_DIR")
+            .map_err(Error::OutDirNotPresent)?
+            .into();
+        let path = out_dir.join(self.out_file.as_deref().unwrap_or("locales.rs"));
+        std::fs::write(&path, prettyplease::unparse(&file)).map_err(Error::WriteOutput)?;
+        Ok(path)
+    }
+}
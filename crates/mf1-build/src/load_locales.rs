@@ -0,0 +1,2938 @@
+use convert_case::Case::{Pascal, Snake};
+use convert_case::Casing;
+use icu_locid::LanguageIdentifier;
+use icu_locid_transform::{LocaleCanonicalizer, LocaleDirectionality};
+use mf1_parser::{
+    parse, plural_category_name, ArgType, LexerSpan, PluralCase, PluralCategory, SelectCase,
+    Token as AstToken, TokenSlice,
+};
+use proc_macro2::Ident;
+use proc_macro2::{Span, TokenStream};
+use quote::quote;
+use serde::{Deserialize, Serialize};
+use std::cell::RefCell;
+use std::io;
+use std::{
+    borrow::Cow,
+    collections::BTreeMap,
+    fs::File,
+    path::{Path, PathBuf},
+};
+use thiserror::Error;
+use toml::Value;
+
+use crate::diagnostics::warn;
+use crate::display_names::autonym;
+use crate::ident::mangle;
+use crate::{key_usage, Builder};
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("Error, can't access env variable \"CARGO_MANIFEST_DIR\": {0}")]
+    CargoDirEnvNotPresent(std::env::VarError),
+    #[error("Error accessing Cargo.toml: {0}")]
+    ManifestNotFound(std::io::Error),
+    #[error("Error parsing Cargo.toml: {0}")]
+    ConfigFileDeser(toml::de::Error),
+    #[error("No locales found")]
+    NoDefaultLocale,
+    #[error("Locale {locale:?} is not a valid BCP 47 language identifier: {err}")]
+    InvalidLocale {
+        locale: String,
+        err: icu_locid::ParserError,
+    },
+    #[error("Locales {first:?} and {second:?} are both {tag}")]
+    DuplicateLocale {
+        first: String,
+        second: String,
+        tag: String,
+    },
+    #[error(
+        "Locale features are enabled, but the feature {feature:?} is not declared in Cargo.toml"
+    )]
+    MissingLocaleFeature { feature: String },
+    #[error("Invalid fallback for locale {locale}: {message}")]
+    InvalidFallback { locale: String, message: String },
+    #[error("Locales {first} and {second} both map to the identifier {ident}")]
+    LocaleCollision {
+        first: String,
+        second: String,
+        ident: String,
+    },
+    #[error("No locale file: {0}")]
+    NoLocaleFile(std::io::Error),
+    #[error("Key {key} is defined in both {first:?} and {second:?}")]
+    DuplicateKey {
+        key: String,
+        first: PathBuf,
+        second: PathBuf,
+    },
+    #[error("Parsing of file {path:?} failed: {err}")]
+    LocaleFileDeser {
+        path: PathBuf,
+        err: serde_json::Error,
+    },
+    #[error("Parsing of key {key} in {locale} failed: {message} ({span:?})")]
+    ParseKeyErr {
+        locale: String,
+        key: String,
+        src: String,
+        message: String,
+        span: LexerSpan,
+    },
+    #[error("Metadata {key:?} in {locale} does not describe any key")]
+    OrphanMetadata { locale: String, key: String },
+    #[error("Key {key} in {locale} is {length} characters long, but has a maxLength of {max}")]
+    MaxLengthExceeded {
+        locale: String,
+        key: String,
+        length: usize,
+        max: usize,
+    },
+    #[error("Key {key} is marked as not translatable, but {locale} translates it")]
+    TranslatedUntranslatable { locale: String, key: String },
+    #[error("Screenshot {path:?} for key {key} does not exist")]
+    MissingScreenshot { key: String, path: PathBuf },
+    #[error("Keys {first:?} and {second:?} both map to the identifier `{ident}`")]
+    KeyCollision {
+        first: String,
+        second: String,
+        ident: String,
+    },
+    #[error(
+        "Argument {arg:?} of key {key} is used as {first_type:?} in {first_locale}, \
+        but as {second_type:?} in {second_locale}"
+    )]
+    ArgTypeConflict {
+        key: String,
+        arg: String,
+        first_locale: String,
+        first_type: ArgType,
+        second_locale: String,
+        second_type: ArgType,
+    },
+    #[error("Argument {arg:?} of key {key} has invalid metadata: {message}")]
+    InvalidArgMetadata {
+        key: String,
+        arg: String,
+        message: String,
+    },
+    #[error("Invalid arguments to load_locales!: {0}")]
+    InvalidArgs(syn::Error),
+    #[error("Error, can't access env variable \"OUT_DIR\": {0}")]
+    OutDirNotPresent(std::env::VarError),
+    #[error("Error formatting the generated code: {0}")]
+    FormatOutput(syn::Error),
+    #[error("Error writing the generated code: {0}")]
+    WriteOutput(std::io::Error),
+    #[error("Error reading {path:?}: {err}")]
+    ReadSource { path: PathBuf, err: std::io::Error },
+    #[error("Parsing of file {path:?} failed: {err}")]
+    ParseSource { path: PathBuf, err: syn::Error },
+    #[error("Unknown error")]
+    Misc,
+}
+
+impl Error {
+    /// A `compile_error!` with the message of this error, which a macro can
+    /// expand to.
+    pub fn to_compile_error(&self) -> TokenStream {
+        let error = self.to_string();
+        quote!(compile_error!(#error);)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct ConfigFile {
+    /// The directory of the locale files, relative to the manifest it is set
+    /// in. Defaults to `locales` next to the manifest of the package.
+    pub locales_dir: Option<String>,
+    pub base_locale: Option<String>,
+    pub locales: Vec<String>,
+    /// Names of locales in their own language, for locales without a
+    /// built-in name.
+    #[serde(default)]
+    pub display_names: BTreeMap<String, String>,
+    /// The locale each locale takes missing messages from, where it isn't the
+    /// nearest listed locale by truncating the tag.
+    #[serde(default)]
+    pub fallbacks: BTreeMap<String, String>,
+    /// Whether the messages of each locale other than the base locale are
+    /// only compiled with a `locale-<tag>` cargo feature.
+    #[serde(default)]
+    pub locale_features: bool,
+    /// How the messages of each locale are generated.
+    #[serde(default)]
+    pub codegen: Codegen,
+    /// Whether to warn about keys which no message macro in `src` uses, and
+    /// about message macros using keys which don't exist.
+    #[serde(default)]
+    pub check_keys: bool,
+}
+
+/// How the messages of each locale are generated, set with `codegen` in
+/// `[package.metadata.mf1]` or as `load_locales!(codegen = "compact")`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Codegen {
+    /// A const `Mf1Keys` struct for each locale, with a formatter for each
+    /// message.
+    #[default]
+    Structs,
+    /// A string table for each locale, with a formatter for each message
+    /// shared between locales. This compiles faster and is smaller for large
+    /// catalogs, but looking up a message goes through the table. With 40
+    /// locales of 3,000 keys, it builds about five times faster and gives a
+    /// binary a third of the size, as measured by the `build` bench of the
+    /// `compact` example.
+    Compact,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct StringSet<'a> {
+    pub name: &'a str,
+    pub keys: BTreeMap<Cow<'a, str>, StringItem<'a>>,
+    /// The locale missing messages are taken from, or `None` for the base
+    /// locale.
+    pub fallback: Option<&'a str>,
+    /// The cargo feature the messages of the locale are compiled with, if
+    /// the locale can be disabled.
+    pub feature: Option<&'a str>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum StringItem<'a> {
+    String(Cow<'a, str>),
+    #[serde(deserialize_with = "deserialize_keys")]
+    Subkey(BTreeMap<Cow<'a, str>, StringItem<'a>>),
+    /// Metadata about the key of the same name, from an `@key` entry.
+    #[serde(skip_deserializing)]
+    Metadata(Metadata),
+}
+
+/// Translator-facing information about a key, given as an `@key` entry
+/// next to the key it describes. Only metadata in the base locale is used.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct Metadata {
+    /// Context for translators, also used as the doc comment of the generated field.
+    pub description: Option<String>,
+    /// The maximum length of the message in characters, not counting arguments.
+    pub max_length: Option<usize>,
+    /// Set to `false` for keys that must be the same in every locale.
+    pub translate: Option<bool>,
+    /// Paths to screenshots of the message in use, relative to the locales directory.
+    #[serde(default)]
+    pub screenshots: Vec<PathBuf>,
+    /// Information about the arguments of the message, by name.
+    #[serde(default)]
+    pub args: BTreeMap<String, ArgMetadata>,
+}
+
+/// Information about an argument of a message.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct ArgMetadata {
+    /// The path of an enum deriving `Mf1Select`, taken by a `select` argument
+    /// instead of a string. Paths are resolved where `load_locales!` is invoked.
+    #[serde(rename = "type")]
+    pub select_type: Option<String>,
+}
+
+/// An argument of a generated message, and the type it is passed as.
+#[derive(Debug, Clone, PartialEq)]
+struct Arg {
+    arg_type: ArgType,
+    /// The path of the `Mf1Select` enum taken by a `select` argument, if any.
+    select_type: Option<String>,
+}
+
+impl Arg {
+    fn select_type(&self) -> Option<syn::Type> {
+        let select_type = self.select_type.as_deref()?;
+        Some(syn::parse_str(select_type).expect("select types are checked when loading"))
+    }
+}
+
+/// Deserialises a map of keys, reading `@key` entries as [`Metadata`].
+pub(crate) fn deserialize_keys<'de, 'a, D>(
+    deserializer: D,
+) -> Result<BTreeMap<Cow<'a, str>, StringItem<'a>>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    struct KeysVisitor<'a>(std::marker::PhantomData<StringItem<'a>>);
+
+    impl<'de, 'a> serde::de::Visitor<'de> for KeysVisitor<'a> {
+        type Value = BTreeMap<Cow<'a, str>, StringItem<'a>>;
+
+        fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+            formatter.write_str("a map of keys")
+        }
+
+        fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+        where
+            A: serde::de::MapAccess<'de>,
+        {
+            let mut keys = BTreeMap::new();
+            while let Some(key) = map.next_key::<String>()? {
+                let item = if key.starts_with('@') {
+                    StringItem::Metadata(map.next_value()?)
+                } else {
+                    map.next_value()?
+                };
+                keys.insert(Cow::Owned(key), item);
+            }
+            Ok(keys)
+        }
+    }
+
+    deserializer.deserialize_map(KeysVisitor(std::marker::PhantomData))
+}
+
+impl<'a> StringSet<'a> {
+    pub fn from_file(name: &'a str, locale_file: File) -> Result<Self, serde_json::Error> {
+        let reader = io::BufReader::new(locale_file);
+        let mut deser = serde_json::Deserializer::from_reader(reader);
+        let keys = deserialize_keys(&mut deser)?;
+        Ok(Self {
+            name,
+            keys,
+            fallback: None,
+            feature: None,
+        })
+    }
+    /// Loads the messages of a locale from `<file>.json` in the locales
+    /// directory, and from the files in the `<file>/` directory. Each of those
+    /// is a namespace named after its path, so `en/settings/profile.json` has
+    /// the keys under `settings.profile`.
+    pub fn load(name: &'a str, locales_dir: &Path, file: &str) -> Result<Self, Error> {
+        let files = locale_files(locales_dir, file).map_err(Error::NoLocaleFile)?;
+        let mut keys = BTreeMap::new();
+        let mut origins = BTreeMap::new();
+        for (namespace, path) in files {
+            let locale_file = File::open(&path).map_err(Error::NoLocaleFile)?;
+            let file_keys = StringSet::from_file(name, locale_file)
+                .map_err(|err| Error::LocaleFileDeser {
+                    path: path.clone(),
+                    err,
+                })?
+                .keys;
+            let file_keys = namespace
+                .into_iter()
+                .rev()
+                .fold(file_keys, |keys, namespace| {
+                    BTreeMap::from([(Cow::Owned(namespace), StringItem::Subkey(keys))])
+                });
+            merge_keys(&mut keys, file_keys, "", &path, &mut origins)?;
+        }
+        Ok(Self {
+            name,
+            keys,
+            fallback: None,
+            feature: None,
+        })
+    }
+    pub fn ident(&self) -> Ident {
+        locale_ident(self.name)
+    }
+    /// Returns every message of the locale by its dotted path, such as
+    /// `nested.keys`.
+    pub fn messages(&self) -> BTreeMap<String, &str> {
+        leaves("", &self.keys)
+            .filter_map(|(path, item)| match item {
+                StringItem::String(message) => Some((path, message.as_ref())),
+                _ => None,
+            })
+            .collect()
+    }
+    /// Returns the metadata of every key by the dotted path of the key.
+    pub fn all_metadata(&self) -> BTreeMap<String, &Metadata> {
+        leaves("", &self.keys)
+            .filter_map(|(path, item)| match item {
+                StringItem::Metadata(metadata) => Some((path, metadata)),
+                _ => None,
+            })
+            .collect()
+    }
+    /// Returns the metadata from the `@key` entry describing `key`, if any.
+    pub fn metadata(&self, key: &str) -> Option<&Metadata> {
+        match self.keys.get(format!("@{}", key).as_str()) {
+            Some(StringItem::Metadata(metadata)) => Some(metadata),
+            _ => None,
+        }
+    }
+}
+
+/// The messages and metadata in a map of keys, by the dotted paths of the
+/// keys they belong to.
+fn leaves<'k, 'a>(
+    prefix: &str,
+    keys: &'k Keys<'a>,
+) -> Box<dyn Iterator<Item = (String, &'k StringItem<'a>)> + 'k> {
+    let prefix = prefix.to_owned();
+    Box::new(keys.iter().flat_map(move |(k, v)| match v {
+        StringItem::Subkey(keys) => leaves(&format!("{}{}.", prefix, k), keys),
+        StringItem::Metadata(_) => {
+            let k = k.strip_prefix('@').unwrap_or(k);
+            Box::new(std::iter::once((format!("{}{}", prefix, k), v)))
+        }
+        StringItem::String(_) => Box::new(std::iter::once((format!("{}{}", prefix, k), v))),
+    }))
+}
+
+/// Finds the files of a locale: `<file>.json` in the locales directory, and
+/// the `.json` files in the `<file>/` directory, with the namespace of each.
+fn locale_files(locales_dir: &Path, file: &str) -> io::Result<Vec<(Vec<String>, PathBuf)>> {
+    let root_file = locales_dir.join(format!("{}.json", file));
+    let dir = locales_dir.join(file);
+    let mut files = Vec::new();
+    if root_file.is_file() || !dir.is_dir() {
+        files.push((Vec::new(), root_file));
+    }
+    if dir.is_dir() {
+        namespace_files(&dir, &mut Vec::new(), &mut files)?;
+    }
+    Ok(files)
+}
+
+/// Finds the `.json` files in the directory of a locale, in sorted order,
+/// with the namespace of each.
+fn namespace_files(
+    dir: &Path,
+    namespace: &mut Vec<String>,
+    files: &mut Vec<(Vec<String>, PathBuf)>,
+) -> io::Result<()> {
+    let mut entries = std::fs::read_dir(dir)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<io::Result<Vec<_>>>()?;
+    entries.sort();
+    for path in entries {
+        let Some(name) = path.file_stem().and_then(|name| name.to_str()) else {
+            continue;
+        };
+        namespace.push(name.to_owned());
+        if path.is_dir() {
+            namespace_files(&path, namespace, files)?;
+        } else if path.extension().is_some_and(|ext| ext == "json") {
+            files.push((namespace.clone(), path.clone()));
+        }
+        namespace.pop();
+    }
+    Ok(())
+}
+
+/// Merges the keys of a file into the keys of its locale, where `origins` has
+/// the file each key was first defined in, by path.
+fn merge_keys<'a>(
+    keys: &mut Keys<'a>,
+    file_keys: Keys<'a>,
+    prefix: &str,
+    path: &Path,
+    origins: &mut BTreeMap<String, PathBuf>,
+) -> Result<(), Error> {
+    for (k, v) in file_keys {
+        let key = format!("{}{}", prefix, k);
+        match (keys.get_mut(&k), v) {
+            (Some(StringItem::Subkey(existing)), StringItem::Subkey(v)) => {
+                merge_keys(existing, v, &format!("{}.", key), path, origins)?;
+            }
+            (Some(_), _) => {
+                // The key was defined by its own file, or with its parent.
+                let first = std::iter::successors(Some(key.as_str()), |k| {
+                    k.rsplit_once('.').map(|(parent, _)| parent)
+                })
+                .find_map(|k| origins.get(k))
+                .cloned()
+                .unwrap_or_default();
+                return Err(Error::DuplicateKey {
+                    key,
+                    first,
+                    second: path.to_owned(),
+                });
+            }
+            (None, v) => {
+                origins.insert(key, path.to_owned());
+                keys.insert(k, v);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// A locale listed in `[package.metadata.mf1]`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LocaleEntry {
+    /// The name of the locale file, without its extension, as it was listed.
+    pub file: String,
+    /// The canonical BCP 47 tag of the locale, such as `en-GB` for `en_UK`.
+    pub tag: String,
+}
+
+/// The catalog configured for a package, with its locales resolved.
+#[derive(Debug, Clone)]
+pub struct CatalogConfig {
+    /// The config, merged from the workspace and package manifests.
+    pub config: ConfigFile,
+    /// The manifest of the package.
+    manifest: toml::Table,
+    pub locales_dir: PathBuf,
+    /// The listed locales, in order.
+    pub locales: Vec<LocaleEntry>,
+    /// The tag of the base locale.
+    pub base_locale: String,
+    /// The locale each locale other than the base locale takes missing
+    /// messages from, by tag.
+    pub fallbacks: BTreeMap<String, String>,
+}
+
+impl CatalogConfig {
+    /// Reads the config of the package in `manifest_dir`.
+    pub fn read(manifest_dir: &Path) -> Result<Self, Error> {
+        Self::read_sources(manifest_dir, &mut Vec::new())
+    }
+
+    /// Reads the config, adding the files it is read from to `sources`.
+    fn read_sources(manifest_dir: &Path, sources: &mut Vec<PathBuf>) -> Result<Self, Error> {
+        sources.push(manifest_dir.join("Cargo.toml"));
+        let manifest = std::fs::read_to_string(manifest_dir.join("Cargo.toml"))
+            .map_err(Error::ManifestNotFound)?
+            .parse::<toml::Table>()
+            .map_err(Error::ConfigFileDeser)?;
+
+        let config = read_config(manifest_dir, &manifest, sources)?;
+
+        let locales_dir: PathBuf = config
+            .locales_dir
+            .clone()
+            .map(|d| d.into())
+            .unwrap_or_else(|| manifest_dir.join("locales"));
+        sources.push(locales_dir.clone());
+
+        let canonicalizer = LocaleCanonicalizer::new();
+        let locales = resolve_locales(&config.locales, &canonicalizer)?;
+
+        let base_locale = match &config.base_locale {
+            Some(base_locale) => canonical_tag(base_locale, &canonicalizer)?,
+            None => locales
+                .first()
+                .map(|entry| entry.tag.clone())
+                .ok_or(Error::NoDefaultLocale)?,
+        };
+        let fallbacks =
+            resolve_fallbacks(&locales, &base_locale, &config.fallbacks, &canonicalizer)?;
+        Ok(CatalogConfig {
+            config,
+            manifest,
+            locales_dir,
+            locales,
+            base_locale,
+            fallbacks,
+        })
+    }
+
+    /// Loads the messages of a locale, with its fallback.
+    pub fn load<'a>(&'a self, locale: &'a LocaleEntry) -> Result<StringSet<'a>, Error> {
+        let mut strings = StringSet::load(&locale.tag, &self.locales_dir, &locale.file)?;
+        strings.fallback = self.fallbacks.get(&locale.tag).map(String::as_str);
+        Ok(strings)
+    }
+
+    /// Finds the files a locale is loaded from.
+    pub fn files(&self, locale: &LocaleEntry) -> Result<Vec<PathBuf>, Error> {
+        let files = locale_files(&self.locales_dir, &locale.file).map_err(Error::NoLocaleFile)?;
+        Ok(files.into_iter().map(|(_, path)| path).collect())
+    }
+}
+
+/// Parses and canonicalises a locale tag, such as `pt_br` into `pt-BR`.
+fn canonical_tag(locale: &str, canonicalizer: &LocaleCanonicalizer) -> Result<String, Error> {
+    let langid = locale
+        .parse::<LanguageIdentifier>()
+        .map_err(|err| Error::InvalidLocale {
+            locale: locale.to_owned(),
+            err,
+        })?;
+    let mut locale = icu_locid::Locale::from(langid);
+    canonicalizer.canonicalize(&mut locale);
+    Ok(locale.id.to_string())
+}
+
+/// The name of the variant and value generated for a locale.
+fn locale_ident(tag: &str) -> Ident {
+    mangle(&tag.to_case(Snake))
+}
+
+/// Canonicalises the listed locales, and checks that no two of them are the
+/// same locale or map to the same identifier.
+fn resolve_locales(
+    names: &[String],
+    canonicalizer: &LocaleCanonicalizer,
+) -> Result<Vec<LocaleEntry>, Error> {
+    let mut entries: Vec<LocaleEntry> = Vec::with_capacity(names.len());
+    for name in names {
+        let tag = canonical_tag(name, canonicalizer)?;
+        for entry in entries.iter() {
+            if entry.tag == tag {
+                return Err(Error::DuplicateLocale {
+                    first: entry.file.clone(),
+                    second: name.clone(),
+                    tag,
+                });
+            }
+            let ident = locale_ident(&tag);
+            if locale_ident(&entry.tag) == ident {
+                return Err(Error::LocaleCollision {
+                    first: entry.tag.clone(),
+                    second: tag,
+                    ident: ident.to_string(),
+                });
+            }
+        }
+        if name.replace('_', "-") != tag {
+            warn(format!("Locale {:?} is canonicalised to {}", name, tag));
+        }
+        entries.push(LocaleEntry {
+            file: name.clone(),
+            tag,
+        });
+    }
+    Ok(entries)
+}
+
+/// Finds the locale each locale takes missing messages from: the one given in
+/// `fallbacks`, or else the nearest listed locale by truncating the tag, so
+/// `es-419` falls back to `es`, or else the base locale.
+///
+/// Returns the fallback of each locale other than the base locale, by tag.
+fn resolve_fallbacks(
+    entries: &[LocaleEntry],
+    base_locale: &str,
+    fallbacks: &BTreeMap<String, String>,
+    canonicalizer: &LocaleCanonicalizer,
+) -> Result<BTreeMap<String, String>, Error> {
+    let is_listed = |tag: &str| entries.iter().any(|entry| entry.tag == tag);
+    let mut explicit = BTreeMap::new();
+    for (locale, fallback) in fallbacks {
+        let invalid = |message: String| Error::InvalidFallback {
+            locale: locale.clone(),
+            message,
+        };
+        let locale = canonical_tag(locale, canonicalizer)?;
+        let fallback = canonical_tag(fallback, canonicalizer)?;
+        if !is_listed(&locale) {
+            return Err(invalid("it is not a listed locale".to_owned()));
+        }
+        if !is_listed(&fallback) {
+            return Err(invalid(format!("{} is not a listed locale", fallback)));
+        }
+        if locale == base_locale {
+            return Err(invalid("the base locale can't have a fallback".to_owned()));
+        }
+        explicit.insert(locale, fallback);
+    }
+
+    let mut resolved = BTreeMap::new();
+    for entry in entries.iter().filter(|entry| entry.tag != base_locale) {
+        let fallback = explicit.get(&entry.tag).cloned().unwrap_or_else(|| {
+            let mut tag = entry.tag.as_str();
+            while let Some((parent, _)) = tag.rsplit_once('-') {
+                if is_listed(parent) {
+                    return parent.to_owned();
+                }
+                tag = parent;
+            }
+            base_locale.to_owned()
+        });
+        resolved.insert(entry.tag.clone(), fallback);
+    }
+
+    // Every chain must reach the base locale.
+    for entry in entries.iter() {
+        let mut chain = vec![entry.tag.as_str()];
+        let mut tag = entry.tag.as_str();
+        while let Some(fallback) = resolved.get(tag) {
+            if chain.contains(&fallback.as_str()) {
+                chain.push(fallback);
+                return Err(Error::InvalidFallback {
+                    locale: entry.tag.clone(),
+                    message: format!("the fallbacks form a cycle: {}", chain.join(" -> ")),
+                });
+            }
+            chain.push(fallback);
+            tag = fallback;
+        }
+    }
+    Ok(resolved)
+}
+
+/// The cargo feature which enables a locale, such as `locale-pt-br`.
+fn locale_feature(tag: &str) -> String {
+    format!("locale-{}", tag.to_lowercase())
+}
+
+/// The name of the module generated for a subkey.
+fn module_ident(key: &str) -> Ident {
+    mangle(&key.to_case(Snake))
+}
+
+/// The name of the struct generated for a subkey.
+fn type_ident(key: &str) -> Ident {
+    mangle(&key.to_case(Pascal))
+}
+
+/// Checks that no two sibling keys map to the same field, module or type name.
+fn check_idents(prefix: &str, keys: &Keys) -> Result<(), Error> {
+    let keys = keys
+        .iter()
+        .filter(|(_, v)| !matches!(v, StringItem::Metadata(_)));
+    let mut seen = [BTreeMap::new(), BTreeMap::new(), BTreeMap::new()];
+    for (k, v) in keys {
+        let path = format!("{}{}", prefix, k);
+        let mut idents = vec![mangle(k)];
+        if let StringItem::Subkey(subkeys) = v {
+            idents.push(module_ident(k));
+            idents.push(type_ident(k));
+            check_idents(&format!("{}.", path), subkeys)?;
+        }
+        for (ident, seen) in idents.into_iter().zip(seen.iter_mut()) {
+            let ident = ident.to_string();
+            if let Some(first) = seen.insert(ident.clone(), path.clone()) {
+                return Err(Error::KeyCollision {
+                    first,
+                    second: path,
+                    ident,
+                });
+            }
+        }
+    }
+    Ok(())
+}
+
+fn doc_attr(metadata: Option<&Metadata>) -> TokenStream {
+    match metadata.and_then(|m| m.description.as_deref()) {
+        Some(description) => quote!(#[doc = #description]),
+        None => quote!(),
+    }
+}
+
+pub(crate) type Keys<'a> = BTreeMap<Cow<'a, str>, StringItem<'a>>;
+
+/// The parsed messages of a locale at one level of keys.
+type ParsedKeys<'k, 's, 'a> = BTreeMap<Cow<'k, str>, Result<Vec<AstToken<'s, 'a, String>>, Error>>;
+
+/// Documents a generated key with its description, base-locale text, the
+/// status of each translation, and its arguments.
+fn key_docs(
+    key: &str,
+    base_locale_strings: &StringSet,
+    locales: &BTreeMap<&str, &StringSet>,
+    args: Option<&BTreeMap<&String, Arg>>,
+) -> TokenStream {
+    let mut lines = Vec::new();
+    if let Some(description) = base_locale_strings
+        .metadata(key)
+        .and_then(|m| m.description.as_deref())
+    {
+        lines.push(format!(" {}", description));
+        lines.push(String::new());
+    }
+    if let Some(StringItem::String(text)) = base_locale_strings.keys.get(key) {
+        lines.push(format!(" Base locale `{}`:", base_locale_strings.name));
+        lines.push(" ```text".to_owned());
+        lines.extend(text.lines().map(|line| format!(" {}", line)));
+        lines.push(" ```".to_owned());
+    }
+    let names: Vec<_> = locales
+        .keys()
+        .filter(|name| **name != base_locale_strings.name)
+        .collect();
+    if !names.is_empty() {
+        lines.push(String::new());
+        lines.push(" Translations:".to_owned());
+        for name in names {
+            let locale = locales[name];
+            let fallback = locale.fallback.unwrap_or(base_locale_strings.name);
+            let mut status = match locale.keys.get(key) {
+                Some(StringItem::String(_)) => "translated".to_owned(),
+                _ => format!("missing, falls back to `{}`", fallback),
+            };
+            if let Some(feature) = locale.feature {
+                status.push_str(&format!(" (only compiled with the `{}` feature)", feature));
+            }
+            lines.push(format!(" - `{}`: {}", name, status));
+        }
+    }
+    if let Some(args) = args.filter(|args| !args.is_empty()) {
+        lines.push(String::new());
+        lines.push(" Arguments:".to_owned());
+        for (arg, arg_type) in args {
+            lines.push(format!(" - `{}`: `{}`", arg, arg_type_name(arg_type)));
+        }
+    }
+    quote!(#(#[doc = #lines])*)
+}
+
+fn arg_type_name(arg: &Arg) -> String {
+    match arg.arg_type {
+        ArgType::OrdinalArg => "impl mf1::Number".to_owned(),
+        ArgType::PlainArg => "impl Display".to_owned(),
+        ArgType::SelectArg => match &arg.select_type {
+            Some(select_type) => format!("&{}", select_type),
+            None => "&str".to_owned(),
+        },
+        ArgType::FunctionArg => unreachable!("function arguments are rejected by the parser"),
+    }
+}
+
+/// The type of an argument in generated code, borrowed for `lifetime`.
+///
+/// Plain arguments can be anything that implements `Display`, and numbers
+/// anything that implements `mf1::Number`, so they are passed as trait objects.
+fn arg_type_tokens(arg: &Arg, lifetime: TokenStream) -> TokenStream {
+    match arg.arg_type {
+        ArgType::OrdinalArg => quote!(&#lifetime dyn mf1::Number),
+        ArgType::PlainArg => quote!(&#lifetime dyn std::fmt::Display),
+        ArgType::SelectArg => match arg.select_type() {
+            Some(select_type) => quote!(&#lifetime #select_type),
+            None => quote!(&#lifetime str),
+        },
+        ArgType::FunctionArg => unreachable!("function arguments are rejected by the parser"),
+    }
+}
+
+/// Gets an argument from the `args` of a message looked up at runtime.
+fn dynamic_arg_tokens(name: &str, arg: &Arg) -> TokenStream {
+    match arg.arg_type {
+        ArgType::OrdinalArg => quote!(mf1::number_arg(args, #name)?),
+        ArgType::PlainArg => quote!(mf1::display_arg(args, #name)?),
+        ArgType::SelectArg => match arg.select_type() {
+            Some(select_type) => quote!(mf1::select_arg::<#select_type>(args, #name)?),
+            None => quote!(mf1::str_arg(args, #name)?),
+        },
+        ArgType::FunctionArg => unreachable!("function arguments are rejected by the parser"),
+    }
+}
+
+/// The message a formatter is generated for.
+struct Message<'m> {
+    locale: &'m str,
+    key: String,
+    args: &'m BTreeMap<&'m String, Arg>,
+    /// The compile-time checks of the `select` arguments with an enum type.
+    checks: RefCell<Vec<TokenStream>>,
+    /// Set for the compact codegen, where the formatter is shared between
+    /// locales and reads its text from the table of the locale.
+    compact: Option<CompactMessage>,
+}
+
+struct CompactMessage {
+    /// The path of the generated `catalog` module.
+    catalog: TokenStream,
+    locale_count: usize,
+    /// The pieces of text of the message, in the order they are written.
+    texts: RefCell<Vec<String>>,
+}
+
+impl<'m> Message<'m> {
+    fn new(locale: &'m str, key: String, args: &'m BTreeMap<&'m String, Arg>) -> Self {
+        Message {
+            locale,
+            key,
+            args,
+            checks: RefCell::new(Vec::new()),
+            compact: None,
+        }
+    }
+}
+
+/// `in_plural` is set inside a `plural` or `selectordinal`, where `#`
+/// refers to its number.
+fn gen_items(token: &AstToken<String>, message: &Message, in_plural: bool) -> TokenStream {
+    match token {
+        AstToken::Content { value } => match &message.compact {
+            Some(compact) => {
+                let mut texts = compact.texts.borrow_mut();
+                let i = texts.len();
+                texts.push(value.clone());
+                quote! {fmt.write_str(__mf1_texts.get(#i))?;}
+            }
+            None => quote! {fmt.write_str(#value)?;},
+        },
+        AstToken::PlainArg { arg } => {
+            let select_type = message.args.get(arg).and_then(Arg::select_type);
+            let arg = mangle(arg);
+            match select_type {
+                // Enums only implement `mf1::Select`, so they are shown as their key.
+                Some(_) => quote! {fmt.write_str(mf1::Select::select_key(#arg))?;},
+                None => quote! {fmt.write_fmt(format_args!("{}", #arg))?;},
+            }
+        }
+        AstToken::Octothorpe {} if in_plural => {
+            quote! {fmt.write_fmt(format_args!("{}", __mf1_value))?;}
+        }
+        AstToken::Octothorpe {} => quote! {fmt.write_str("#")?;},
+        select @ AstToken::Select {
+            arg,
+            cases,
+            plural_offset: _,
+        } => {
+            let case_statements = cases.iter().filter(|case| case.key != "other").map(|case| {
+                let key = &case.key;
+                let items = case.tokens.iter().map(|t| gen_items(t, message, in_plural));
+                quote! { #key => { #(#items)* }}
+            });
+            let select_type = message.args.get(arg).and_then(Arg::select_type);
+            let other = if let Some(case) = cases.iter().find(|case| case.key == "other") {
+                let items = case.tokens.iter().map(|t| gen_items(t, message, in_plural));
+                quote! { _ => { #(#items)* }
+                }
+            } else if select_type.is_some() {
+                // Every variant is covered, which is checked below.
+                quote! { _ => unreachable!() }
+            } else {
+                warn(format!("Missing other case in select {select:?}"));
+                quote! {}
+            };
+            if let Some(select_type) = &select_type {
+                let checks = gen_select_checks(arg, cases, select_type, message);
+                message.checks.borrow_mut().push(checks);
+            }
+            let arg = mangle(arg);
+            let scrutinee = match select_type {
+                Some(_) => quote!(mf1::Select::select_key(#arg)),
+                None => quote!(#arg),
+            };
+            quote! {
+                match #scrutinee {
+                    #(#case_statements,)*
+                    #other
+                }
+            }
+        }
+        AstToken::Plural {
+            arg,
+            cases,
+            plural_offset,
+        } => gen_plural(arg, cases, *plural_offset, false, message),
+        AstToken::SelectOrdinal {
+            arg,
+            cases,
+            plural_offset,
+        } => gen_plural(arg, cases, *plural_offset, true, message),
+        AstToken::FunctionArg { .. } => {
+            unreachable!("function arguments are rejected by the parser")
+        }
+    }
+}
+
+/// Checks at compile time that the cases of a select match the keys of its enum.
+fn gen_select_checks(
+    arg: &String,
+    cases: &[SelectCase<String>],
+    select_type: &syn::Type,
+    message: &Message,
+) -> TokenStream {
+    let type_name = message.args[arg].select_type.as_deref().unwrap_or_default();
+    let keys: Vec<_> = cases
+        .iter()
+        .map(|case| case.key.as_str())
+        .filter(|key| *key != "other")
+        .collect();
+    let has_key_checks = keys.iter().map(|key| {
+        let error = format!(
+            "Select {:?} of key {} in {} has a case {:?}, which is not a key of `{}`",
+            arg, message.key, message.locale, key, type_name
+        );
+        quote!(assert!(mf1::select_has_key::<#select_type>(#key), "{}", #error);)
+    });
+    let covers_check = (keys.len() == cases.len()).then(|| {
+        let error = format!(
+            "Select {:?} of key {} in {} does not cover every variant of `{}`, and has no `other` case",
+            arg, message.key, message.locale, type_name
+        );
+        quote!(assert!(mf1::select_covers::<#select_type>(&[#(#keys),*]), "{}", #error);)
+    });
+    quote! {
+        const _: () = {
+            #(#has_key_checks)*
+            #covers_check
+        };
+    }
+}
+
+fn gen_plural(
+    arg: &str,
+    cases: &[PluralCase<String>],
+    plural_offset: Option<i32>,
+    ordinal: bool,
+    message: &Message,
+) -> TokenStream {
+    let case_statements = cases
+        .iter()
+        .filter(|case| case.key != PluralCategory::Other)
+        .map(|case| {
+            let category =
+                quote::format_ident!("{}", plural_category_name(case.key).to_case(Pascal));
+            let items = case.tokens.iter().map(|t| gen_items(t, message, true));
+            quote! { mf1::icu_plurals::PluralCategory::#category => { #(#items)* }}
+        });
+    let other = match cases.iter().find(|case| case.key == PluralCategory::Other) {
+        Some(case) => {
+            let items = case.tokens.iter().map(|t| gen_items(t, message, true));
+            quote! { _ => { #(#items)* } }
+        }
+        None => {
+            warn(format!("Missing other case in plural {arg:?}"));
+            quote! { _ => {} }
+        }
+    };
+    let arg = mangle(arg);
+    let offset = plural_offset.unwrap_or(0);
+    // The compact formatters are shared between locales, so they cache the
+    // rules of each locale they are used for.
+    let rules = match &message.compact {
+        Some(CompactMessage {
+            catalog,
+            locale_count,
+            ..
+        }) => quote! {
+            static __MF1_RULES: [std::sync::OnceLock<mf1::icu_plurals::PluralRules>; #locale_count] =
+                [const { std::sync::OnceLock::new() }; #locale_count];
+            let __mf1_rules = __MF1_RULES[__mf1_locale]
+                .get_or_init(|| mf1::plural_rules(#catalog::CATALOG.tags[__mf1_locale], #ordinal));
+        },
+        None => {
+            let locale = message.locale;
+            quote! {
+                static __MF1_RULES: std::sync::OnceLock<mf1::icu_plurals::PluralRules> = std::sync::OnceLock::new();
+                let __mf1_rules = __MF1_RULES.get_or_init(|| mf1::plural_rules(#locale, #ordinal));
+            }
+        }
+    };
+    quote! {{
+        #rules
+        let __mf1_value = mf1::plural_value(#arg, #offset);
+        match __mf1_rules.category_for(&__mf1_value) {
+            #(#case_statements,)*
+            #other
+        }
+    }}
+}
+
+/// The string tables of the compact codegen, filled in by `generate_keys`.
+struct CompactTables {
+    /// The tag of each locale, in the order of `Locale::VALUES`.
+    tags: Vec<String>,
+    /// The table of each locale, in the same order.
+    tables: Vec<TableBuilder>,
+    key_count: u32,
+}
+
+#[derive(Default)]
+struct TableBuilder {
+    text: String,
+    /// The start of each distinct piece of text, which is only stored once.
+    pieces: BTreeMap<String, u32>,
+    spans: Vec<(u32, u32)>,
+    /// The shape and first span of each message, by key.
+    messages: BTreeMap<u32, (u16, u32)>,
+}
+
+impl CompactTables {
+    fn new(tags: Vec<String>) -> Self {
+        let tables = tags.iter().map(|_| TableBuilder::default()).collect();
+        CompactTables {
+            tags,
+            tables,
+            key_count: 0,
+        }
+    }
+
+    fn next_key(&mut self) -> u32 {
+        self.key_count += 1;
+        self.key_count - 1
+    }
+
+    fn insert(&mut self, locale: &str, key: u32, shape: u16, texts: &[String]) {
+        let index = self.locale_index(locale);
+        let table = &mut self.tables[index];
+        let first_span = table.spans.len() as u32;
+        for text in texts {
+            let start = *table.pieces.entry(text.clone()).or_insert_with(|| {
+                table.text.push_str(text);
+                (table.text.len() - text.len()) as u32
+            });
+            table.spans.push((start, start + text.len() as u32));
+        }
+        table.messages.insert(key, (shape, first_span));
+    }
+
+    fn locale_index(&self, locale: &str) -> usize {
+        self.tags
+            .iter()
+            .position(|tag| tag == locale)
+            .expect("every locale has a table")
+    }
+
+    /// Generates the `catalog` module with the tables, which is used through
+    /// the message handles of `Mf1Keys`.
+    fn generate(&self, locales: &[StringSet]) -> TokenStream {
+        let tables = locales.iter().zip(&self.tables).map(|(locale, table)| {
+            let ident = locale.ident();
+            let text = &table.text;
+            let spans = table
+                .spans
+                .iter()
+                .map(|(start, end)| quote!((#start, #end)));
+            let messages = (0..self.key_count).map(|key| match table.messages.get(&key) {
+                Some((shape, first_span)) => quote! {
+                    mf1::compact::Entry { shape: #shape, first_span: #first_span }
+                },
+                None => quote!(mf1::compact::Entry::MISSING),
+            });
+            let value = quote! {
+                #[allow(non_upper_case_globals)]
+                const #ident: mf1::compact::LocaleTable = mf1::compact::LocaleTable {
+                    text: #text,
+                    spans: &[#(#spans,)*],
+                    messages: &[#(#messages,)*],
+                };
+            };
+            match locale.feature {
+                // A disabled locale takes every message from its fallback.
+                Some(feature) => quote! {
+                    #[cfg(feature = #feature)]
+                    #value
+                    #[cfg(not(feature = #feature))]
+                    #[allow(non_upper_case_globals)]
+                    const #ident: mf1::compact::LocaleTable = mf1::compact::LocaleTable::EMPTY;
+                },
+                None => value,
+            }
+        });
+        let idents = locales.iter().map(StringSet::ident);
+        let tags = &self.tags;
+        let fallbacks = locales.iter().enumerate().map(|(i, locale)| {
+            let fallback = locale.fallback.map_or(i, |tag| self.locale_index(tag)) as u16;
+            quote!(#fallback)
+        });
+        quote! {
+            #[doc(hidden)]
+            pub mod catalog {
+                #(#tables)*
+
+                pub static CATALOG: mf1::compact::Catalog = mf1::compact::Catalog {
+                    tags: &[#(#tags,)*],
+                    fallbacks: &[#(#fallbacks,)*],
+                    locales: &[#(#idents,)*],
+                };
+
+                /// A message without arguments, read from the string table of
+                /// its locale.
+                #[derive(Copy, Clone, Debug, Hash, PartialEq, Eq)]
+                pub struct Text<const KEY: u32> {
+                    locale: u16,
+                }
+
+                impl<const KEY: u32> Text<KEY> {
+                    pub const fn new(locale: u16) -> Self {
+                        Text { locale }
+                    }
+
+                    /// The text of the message.
+                    pub fn as_str(self) -> &'static str {
+                        CATALOG.string(self.locale, KEY)
+                    }
+                }
+
+                impl<const KEY: u32> mf1::BuildStr for Text<KEY> {
+                    #[inline]
+                    fn build_string(self) -> std::borrow::Cow<'static, str> {
+                        std::borrow::Cow::Borrowed(self.as_str())
+                    }
+                }
+
+                impl<const KEY: u32> mf1::Message for Text<KEY> {
+                    fn write_to(&self, out: &mut dyn mf1::Formattable) -> std::fmt::Result {
+                        out.write_str(self.as_str())
+                    }
+                }
+
+                impl<const KEY: u32> std::fmt::Display for Text<KEY> {
+                    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                        f.write_str(self.as_str())
+                    }
+                }
+
+                impl<const KEY: u32> mf1::DynamicMessage for Text<KEY> {
+                    fn arg_names(&self) -> &'static [&'static str] {
+                        &[]
+                    }
+
+                    fn write_dynamic(&self, out: &mut dyn mf1::Formattable, _: &dyn mf1::Args) -> Result<(), mf1::FormatError> {
+                        Ok(out.write_str(self.as_str())?)
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// The messages of one level of keys in the compact codegen.
+struct CompactMessages {
+    /// The index of each message in the tables, by key.
+    keys: BTreeMap<String, u32>,
+    /// The formatter of each message with arguments, by key.
+    formatters: BTreeMap<String, TokenStream>,
+}
+
+/// Adds the messages of one level of keys to the string tables, and generates
+/// the formatters of the messages with arguments.
+///
+/// A formatter has a case for each distinct shape its message has across
+/// locales, so locales which only differ in their text share the same code.
+fn compact_messages(
+    prefix: &str,
+    tables: &mut CompactTables,
+    catalog: &TokenStream,
+    locales: &BTreeMap<&str, &StringSet>,
+    locale_ast: &BTreeMap<&&str, ParsedKeys>,
+    string_keys: &[&Cow<str>],
+    dyn_keys: &BTreeMap<&Cow<str>, BTreeMap<&String, Arg>>,
+) -> CompactMessages {
+    let mut keys = BTreeMap::new();
+    for key in string_keys {
+        let index = tables.next_key();
+        for (name, l) in locales {
+            if let Some(StringItem::String(value)) = l.keys.get(*key) {
+                tables.insert(name, index, 0, &[value.to_string()]);
+            }
+        }
+        keys.insert(key.to_string(), index);
+    }
+
+    let mut formatters = BTreeMap::new();
+    for (key, arg_types) in dyn_keys {
+        let index = tables.next_key();
+        let mut shapes: Vec<String> = Vec::new();
+        let mut cases = Vec::new();
+        let mut checks = Vec::new();
+        for (name, asts) in locale_ast {
+            let Some(Ok(ast)) = asts.get(*key) else {
+                continue;
+            };
+            let mut message = Message::new(name, format!("{}{}", prefix, key), arg_types);
+            message.compact = Some(CompactMessage {
+                catalog: catalog.clone(),
+                locale_count: tables.tags.len(),
+                texts: RefCell::new(Vec::new()),
+            });
+            let items: Vec<_> = ast.iter().map(|t| gen_items(t, &message, false)).collect();
+            let body = quote!(#(#items)*);
+            let shape = body.to_string();
+            let shape = match shapes.iter().position(|s| *s == shape) {
+                Some(shape) => shape as u16,
+                None => {
+                    shapes.push(shape);
+                    cases.push(body);
+                    (shapes.len() - 1) as u16
+                }
+            };
+            let texts = message.compact.take().unwrap().texts.into_inner();
+            tables.insert(name, index, shape, &texts);
+            checks.extend(message.checks.into_inner());
+        }
+
+        let ident = quote::format_ident!("__mf1_format_{}", mangle(key));
+        let args = arg_types.iter().map(|(name, arg_type)| {
+            let name = mangle(name);
+            let arg_type = arg_type_tokens(arg_type, quote!());
+            quote!(#name: #arg_type)
+        });
+        let shape_indices = (0..cases.len() as u16).collect::<Vec<_>>();
+        formatters.insert(
+            key.to_string(),
+            quote! {
+                #(#checks)*
+
+                #[allow(non_snake_case, unused_variables)]
+                fn #ident(fmt: &mut dyn mf1::Formattable, locale: u16, #(#args,)*) -> std::fmt::Result {
+                    let (__mf1_locale, __mf1_shape, __mf1_texts) = #catalog::CATALOG.message(locale, #index);
+                    match __mf1_shape {
+                        #(#shape_indices => { #cases })*
+                        _ => unreachable!(),
+                    }
+                    Ok(())
+                }
+            },
+        );
+        keys.insert(key.to_string(), index);
+    }
+    CompactMessages { keys, formatters }
+}
+
+/// Checks the `@key` metadata of the base locale against the messages of every locale.
+fn validate_metadata(
+    prefix: &str,
+    base_keys: &Keys,
+    locales: &[(&str, Option<&Keys>)],
+    base_locale: &str,
+    locales_dir: &Path,
+) -> Result<(), Error> {
+    for (k, v) in base_keys.iter() {
+        match v {
+            StringItem::Metadata(metadata) => {
+                let name = &k[1..];
+                let path = format!("{}{}", prefix, name);
+                match base_keys.get(name) {
+                    Some(StringItem::String(_) | StringItem::Subkey(_)) => {}
+                    _ => {
+                        return Err(Error::OrphanMetadata {
+                            locale: base_locale.to_string(),
+                            key: path,
+                        })
+                    }
+                }
+                let base_value = base_keys.get(name);
+                for (locale, keys) in locales {
+                    let Some(StringItem::String(value)) = keys.and_then(|keys| keys.get(name))
+                    else {
+                        continue;
+                    };
+                    if let (Some(max), Ok(ast)) = (metadata.max_length, parse::<&str>(value)) {
+                        let length = literal_length(&ast);
+                        if length > max {
+                            return Err(Error::MaxLengthExceeded {
+                                locale: locale.to_string(),
+                                key: path,
+                                length,
+                                max,
+                            });
+                        }
+                    }
+                    if metadata.translate == Some(false)
+                        && *locale != base_locale
+                        && Some(&StringItem::String(value.clone())) != base_value
+                    {
+                        return Err(Error::TranslatedUntranslatable {
+                            locale: locale.to_string(),
+                            key: path,
+                        });
+                    }
+                }
+                for screenshot in metadata.screenshots.iter() {
+                    if !locales_dir.join(screenshot).exists() {
+                        return Err(Error::MissingScreenshot {
+                            key: path,
+                            path: screenshot.clone(),
+                        });
+                    }
+                }
+            }
+            StringItem::Subkey(keys) => {
+                let sub_locales: Vec<_> = locales
+                    .iter()
+                    .map(|(locale, locale_keys)| {
+                        let sub = match locale_keys.and_then(|l| l.get(k)) {
+                            Some(StringItem::Subkey(sub)) => Some(sub),
+                            _ => None,
+                        };
+                        (*locale, sub)
+                    })
+                    .collect();
+                validate_metadata(
+                    &format!("{}{}.", prefix, k),
+                    keys,
+                    &sub_locales,
+                    base_locale,
+                    locales_dir,
+                )?;
+            }
+            StringItem::String(_) => {}
+        }
+    }
+    Ok(())
+}
+
+/// The number of characters in a message, not counting arguments. For
+/// `select` and `plural` arguments, the longest case is counted.
+fn literal_length(tokens: &[AstToken<&str>]) -> usize {
+    tokens
+        .iter()
+        .map(|token| match token {
+            AstToken::Content { value } => value.chars().count(),
+            AstToken::Select { cases, .. } => cases
+                .iter()
+                .map(|c| literal_length(&c.tokens))
+                .max()
+                .unwrap_or(0),
+            AstToken::Plural { cases, .. } | AstToken::SelectOrdinal { cases, .. } => cases
+                .iter()
+                .map(|c| literal_length(&c.tokens))
+                .max()
+                .unwrap_or(0),
+            AstToken::PlainArg { .. } | AstToken::FunctionArg { .. } | AstToken::Octothorpe {} => 0,
+        })
+        .sum()
+}
+
+/// Reads the config of a package from `[workspace.metadata.mf1]` in the root
+/// manifest of its workspace, overridden key by key by
+/// `[package.metadata.mf1]` in its own manifest.
+///
+/// A `locales_dir` is relative to the manifest it is set in.
+fn read_config(
+    package_dir: &Path,
+    manifest: &toml::Table,
+    sources: &mut Vec<PathBuf>,
+) -> Result<ConfigFile, Error> {
+    fn mf1_table<'t>(manifest: &'t toml::Table, section: &str) -> Option<&'t toml::Table> {
+        manifest
+            .get(section)?
+            .get("metadata")?
+            .get("mf1")?
+            .as_table()
+    }
+    let mut config = toml::Table::new();
+    let mut merge = |table: &toml::Table, dir: &Path| {
+        config.extend(table.clone());
+        if let Some(Value::String(locales_dir)) = table.get("locales_dir") {
+            let locales_dir = dir.join(locales_dir).to_string_lossy().into_owned();
+            config.insert("locales_dir".to_owned(), Value::String(locales_dir));
+        }
+    };
+    if let Some((root, workspace)) = workspace_manifest(package_dir, manifest)? {
+        sources.push(root.join("Cargo.toml"));
+        if let Some(table) = mf1_table(&workspace, "workspace") {
+            merge(table, &root);
+        }
+    }
+    if let Some(table) = mf1_table(manifest, "package") {
+        merge(table, package_dir);
+    }
+    Ok(Value::Table(config).try_into().unwrap_or_default())
+}
+
+/// Finds the root manifest of the workspace of a package: its own manifest,
+/// or the nearest one above it with a `[workspace]` table.
+fn workspace_manifest(
+    package_dir: &Path,
+    manifest: &toml::Table,
+) -> Result<Option<(PathBuf, toml::Table)>, Error> {
+    if manifest.contains_key("workspace") {
+        return Ok(Some((package_dir.to_owned(), manifest.clone())));
+    }
+    for dir in package_dir.ancestors().skip(1) {
+        let path = dir.join("Cargo.toml");
+        if !path.is_file() {
+            continue;
+        }
+        let root = std::fs::read_to_string(&path)
+            .map_err(Error::ManifestNotFound)?
+            .parse::<toml::Table>()
+            .map_err(Error::ConfigFileDeser)?;
+        if root.contains_key("workspace") {
+            return Ok(Some((dir.to_owned(), root)));
+        }
+    }
+    Ok(None)
+}
+
+/// Warns about the keys of the base locale which no message macro in the
+/// `src` directory of the crate uses, and about the macros which use keys that
+/// don't exist.
+fn check_keys(manifest_dir: &Path, base_locale: &StringSet, sources: &mut Vec<PathBuf>) {
+    let src_dir = manifest_dir.join("src");
+    sources.push(src_dir.clone());
+    let usages = match key_usage::scan_dir(&src_dir) {
+        Ok(usages) => usages,
+        Err(err) => {
+            warn(format!("Can't check which keys are used: {}", err));
+            return;
+        }
+    };
+    let report = key_usage::compare(base_locale, usages);
+    for key in report.unused {
+        warn(format!("Key {} is not used by any message macro", key));
+    }
+    for usage in report.undefined {
+        warn(format!(
+            "Key {} used in {}:{}:{} is not in the base locale",
+            usage.path(),
+            usage.file.display(),
+            usage.line,
+            usage.column
+        ));
+    }
+}
+
+/// Parses the arguments of `load_locales!`, which can override the `codegen`
+/// of the config.
+pub fn parse_args(args: TokenStream) -> Result<Option<Codegen>, Error> {
+    if args.is_empty() {
+        return Ok(None);
+    }
+    let parser = |input: syn::parse::ParseStream| {
+        let name: Ident = input.parse()?;
+        if name != "codegen" {
+            return Err(syn::Error::new(name.span(), "expected `codegen`"));
+        }
+        input.parse::<syn::Token![=]>()?;
+        let value: syn::LitStr = input.parse()?;
+        match value.value().as_str() {
+            "structs" => Ok(Codegen::Structs),
+            "compact" => Ok(Codegen::Compact),
+            _ => Err(syn::Error::new(
+                value.span(),
+                "expected \"structs\" or \"compact\"",
+            )),
+        }
+    };
+    syn::parse::Parser::parse2(parser, args)
+        .map(Some)
+        .map_err(Error::InvalidArgs)
+}
+
+/// Generates the code for the catalog configured in the manifest of the
+/// builder, adding the files it is generated from to `sources`.
+pub(crate) fn load_locales(
+    builder: &Builder,
+    sources: &mut Vec<PathBuf>,
+) -> Result<TokenStream, Error> {
+    let cargo_manifest_dir: PathBuf = match &builder.manifest_dir {
+        Some(dir) => dir.clone(),
+        None => std::env::var("CARGO_MANIFEST_DIR")
+            .map_err(Error::CargoDirEnvNotPresent)?
+            .into(),
+    };
+
+    let catalog = CatalogConfig::read_sources(&cargo_manifest_dir, sources)?;
+    let meta = &catalog.config;
+    let manifest_dir_path = &catalog.locales_dir;
+    let entries = &catalog.locales;
+    let default_locale = &catalog.base_locale;
+
+    let features = entries
+        .iter()
+        .map(|entry| {
+            if !meta.locale_features || entry.tag == *default_locale {
+                return Ok(None);
+            }
+            let feature = locale_feature(&entry.tag);
+            let declared = catalog
+                .manifest
+                .get("features")
+                .and_then(|features| features.get(&feature))
+                .is_some();
+            if !declared {
+                return Err(Error::MissingLocaleFeature { feature });
+            }
+            Ok(Some(feature))
+        })
+        .collect::<Result<Vec<_>, Error>>()?;
+
+    let mut locales = Vec::with_capacity(entries.len());
+    for (entry, feature) in entries.iter().zip(&features) {
+        let mut locale = catalog.load(entry)?;
+        locale.feature = feature.as_deref();
+        locales.push(locale);
+    }
+
+    let i18n_keys_ident = quote::format_ident!("Mf1Keys");
+
+    let base_locale_strings = locales
+        .iter()
+        .find(|l| l.name == default_locale)
+        .ok_or(Error::NoDefaultLocale)?;
+    let base_locale_ident = base_locale_strings.ident();
+
+    check_idents("", &base_locale_strings.keys)?;
+    validate_metadata(
+        "",
+        &base_locale_strings.keys,
+        &locales
+            .iter()
+            .map(|l| (l.name, Some(&l.keys)))
+            .collect::<Vec<_>>(),
+        base_locale_strings.name,
+        manifest_dir_path,
+    )?;
+    if builder.check_keys.unwrap_or(meta.check_keys) {
+        check_keys(&cargo_manifest_dir, base_locale_strings, sources);
+    }
+
+    let locale_idents: Vec<_> = locales.iter().map(StringSet::ident).collect();
+
+    let get_strings_match_arms = locale_idents
+        .iter()
+        .map(|locale| quote!(Locale::#locale => &#locale));
+
+    let as_str_match_arms = locale_idents
+        .iter()
+        .zip(locales.iter())
+        .map(|(key, l)| (key, l.name))
+        .map(|(variant, locale)| quote!(Locale::#variant => #locale));
+
+    // Each locale is parsed from its tag, and from the name it was listed as.
+    let from_str_checks = locale_idents
+        .iter()
+        .zip(entries.iter())
+        .map(|(variant, entry)| {
+            let aliases =
+                std::iter::once(&entry.tag).chain((entry.file != entry.tag).then_some(&entry.file));
+            quote! {
+                if #(mf1::locale_tag_eq(s, #aliases))||* {
+                    return Ok(Locale::#variant);
+                }
+            }
+        });
+
+    let locale_list_items = locale_idents.iter().map(|variant| quote!(Locale::#variant));
+    let locale_names: Vec<_> = locales.iter().map(|l| l.name).collect();
+
+    let langids = locales
+        .iter()
+        .map(|l| {
+            l.name
+                .parse::<LanguageIdentifier>()
+                .map_err(|err| Error::InvalidLocale {
+                    locale: l.name.to_owned(),
+                    err,
+                })
+        })
+        .collect::<Result<Vec<_>, Error>>()?;
+    let directionality = LocaleDirectionality::new();
+    let display_name_match_arms =
+        locale_idents
+            .iter()
+            .zip(entries)
+            .zip(&langids)
+            .map(|((variant, entry), langid)| {
+                let name = match meta
+                    .display_names
+                    .get(&entry.tag)
+                    .or_else(|| meta.display_names.get(&entry.file))
+                {
+                    Some(name) => name.as_str(),
+                    None => autonym(langid).unwrap_or(&entry.tag),
+                };
+                quote!(Locale::#variant => #name)
+            });
+    let is_enabled_match_arms = locale_idents.iter().zip(&locales).map(|(variant, l)| {
+        let enabled = match l.feature {
+            Some(feature) => quote!(cfg!(feature = #feature)),
+            None => quote!(true),
+        };
+        quote!(Locale::#variant => #enabled)
+    });
+    let text_direction_match_arms = locale_idents.iter().zip(&langids).map(|(variant, langid)| {
+        let direction = if directionality.is_right_to_left(langid) {
+            quote!(RightToLeft)
+        } else {
+            quote!(LeftToRight)
+        };
+        quote!(Locale::#variant => mf1::TextDirection::#direction)
+    });
+    let locale_count = locale_idents.len();
+    // The base locale is the default.
+    let default_attrs = locale_idents
+        .iter()
+        .map(|ident| (*ident == base_locale_ident).then(|| quote!(#[default])));
+
+    let current_locale = if builder.ambient_locale {
+        generate_current_locale()
+    } else {
+        quote!()
+    };
+    let serde_impls = if builder.serde {
+        generate_serde_impls(&locale_names)
+    } else {
+        quote!()
+    };
+
+    let mut compact = match builder.codegen.unwrap_or(meta.codegen) {
+        Codegen::Structs => None,
+        Codegen::Compact => Some(CompactTables::new(
+            locale_names.iter().map(|name| name.to_string()).collect(),
+        )),
+    };
+    let mut key_infos = BTreeMap::new();
+    let keys_tokens = generate_keys(
+        "",
+        locales.iter().map(|k| (k.name, k)).collect(),
+        base_locale_strings,
+        i18n_keys_ident.clone(),
+        &mut key_infos,
+        compact.as_mut(),
+    )?;
+    let catalog = compact.map(|tables| tables.generate(&locales));
+    let key_infos = key_infos.values();
+
+    let locales_enum = quote! {
+        #[derive(Copy, Clone, Debug, Default, Hash, PartialEq, Eq)]
+        #[allow(non_camel_case_types)]
+        pub enum Locale {
+            #(#default_attrs #locale_idents,)*
+        }
+
+        impl Locale {
+            /// Every locale of the catalog.
+            pub const VALUES: [Self; #locale_count] = [#(#locale_list_items,)*];
+
+            /// Every message of the catalog, sorted by path.
+            pub const KEYS: &'static [mf1::KeyInfo<Locale>] = &[#(#key_infos,)*];
+
+            /// Iterates over every locale of the catalog.
+            pub fn iter() -> std::array::IntoIter<Self, #locale_count> {
+                Self::VALUES.into_iter()
+            }
+
+            /// The messages of this locale.
+            pub fn get_strings(self) -> &'static #i18n_keys_ident {
+                match self {
+                    #(#get_strings_match_arms,)*
+                }
+            }
+
+            /// The tag of this locale, such as `en`.
+            pub fn as_str(self) -> &'static str {
+                match self {
+                    #(#as_str_match_arms,)*
+                }
+            }
+
+            /// Whether the messages of this locale are compiled in. A disabled
+            /// locale uses the messages of its fallback locale.
+            pub fn is_enabled(self) -> bool {
+                match self {
+                    #(#is_enabled_match_arms,)*
+                }
+            }
+
+            /// The name of this locale in its own language, such as `Español`,
+            /// for locale pickers.
+            pub fn display_name(self) -> &'static str {
+                match self {
+                    #(#display_name_match_arms,)*
+                }
+            }
+
+            /// The direction of the script this locale is written in.
+            pub fn text_direction(self) -> mf1::TextDirection {
+                match self {
+                    #(#text_direction_match_arms,)*
+                }
+            }
+        }
+
+        impl std::str::FromStr for Locale {
+            type Err = mf1::ParseLocaleError;
+
+            fn from_str(s: &str) -> Result<Self, Self::Err> {
+                let s = s.trim();
+                #(#from_str_checks)*
+                Err(mf1::ParseLocaleError::new(s, &[#(#locale_names,)*]))
+            }
+        }
+
+        impl TryFrom<&str> for Locale {
+            type Error = mf1::ParseLocaleError;
+
+            fn try_from(s: &str) -> Result<Self, Self::Error> {
+                s.parse()
+            }
+        }
+
+        impl From<Locale> for mf1::icu_locid::LanguageIdentifier {
+            fn from(locale: Locale) -> Self {
+                locale
+                    .as_str()
+                    .parse()
+                    .expect("locales are checked by load_locales!")
+            }
+        }
+
+        #current_locale
+        #serde_impls
+    };
+    Ok(quote! {
+        #locales_enum
+        #keys_tokens
+        #catalog
+    })
+}
+
+/// Generates `Serialize` and `Deserialize` impls for `Locale`, which use its
+/// tag and accept whatever `FromStr` accepts.
+fn generate_serde_impls(locale_names: &[&str]) -> TokenStream {
+    let expecting = format!("one of the locales {}", locale_names.join(", "));
+    quote! {
+        impl mf1::serde::Serialize for Locale {
+            fn serialize<S: mf1::serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                serializer.serialize_str(self.as_str())
+            }
+        }
+
+        impl<'de> mf1::serde::Deserialize<'de> for Locale {
+            fn deserialize<D: mf1::serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                struct LocaleVisitor;
+
+                impl mf1::serde::de::Visitor<'_> for LocaleVisitor {
+                    type Value = Locale;
+
+                    fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                        f.write_str(#expecting)
+                    }
+
+                    fn visit_str<E: mf1::serde::de::Error>(self, s: &str) -> Result<Locale, E> {
+                        s.parse().map_err(|_| E::invalid_value(mf1::serde::de::Unexpected::Str(s), &self))
+                    }
+                }
+
+                deserializer.deserialize_str(LocaleVisitor)
+            }
+        }
+    }
+}
+
+/// Generates the thread-local current locale used by `t_string!` and friends.
+fn generate_current_locale() -> TokenStream {
+    quote! {
+        thread_local! {
+            static CURRENT_LOCALE: std::cell::Cell<Locale> = std::cell::Cell::new(Locale::default());
+        }
+
+        impl Locale {
+            /// Returns the current locale of this thread, which is the default
+            /// locale unless another one has been set.
+            pub fn current() -> Self {
+                CURRENT_LOCALE.with(std::cell::Cell::get)
+            }
+
+            /// Sets the current locale of this thread.
+            pub fn set_current(self) {
+                CURRENT_LOCALE.with(|current| current.set(self))
+            }
+
+            /// Makes this the current locale of this thread until the returned
+            /// guard is dropped.
+            pub fn enter(self) -> LocaleGuard {
+                LocaleGuard {
+                    previous: CURRENT_LOCALE.with(|current| current.replace(self)),
+                    _not_send: std::marker::PhantomData,
+                }
+            }
+
+            /// Runs `f` with this as the current locale of this thread.
+            pub fn scope<R>(self, f: impl FnOnce() -> R) -> R {
+                let _guard = self.enter();
+                f()
+            }
+        }
+
+        /// Restores the previous current locale when dropped.
+        ///
+        /// Returned by [`Locale::enter`].
+        #[must_use = "the locale is only current until the guard is dropped"]
+        pub struct LocaleGuard {
+            previous: Locale,
+            // The guard restores the locale of the thread it was created on.
+            _not_send: std::marker::PhantomData<*const ()>,
+        }
+
+        impl Drop for LocaleGuard {
+            fn drop(&mut self) {
+                CURRENT_LOCALE.with(|current| current.set(self.previous))
+            }
+        }
+    }
+}
+
+/// Generates the keys struct and its values for each locale.
+///
+/// The `mf1::KeyInfo` of each message is added to `key_infos`, by path.
+fn generate_keys(
+    prefix: &str,
+    locales: BTreeMap<&str, &StringSet>,
+    base_locale_strings: &StringSet,
+    i18n_keys_ident: Ident,
+    key_infos: &mut BTreeMap<String, TokenStream>,
+    mut compact: Option<&mut CompactTables>,
+) -> Result<TokenStream, Error> {
+    let base_locale_ident = base_locale_strings.ident();
+    // The `catalog` module of the compact codegen is generated next to
+    // `Mf1Keys`, and each level of keys is two modules deeper.
+    let root = (0..prefix.matches('.').count() * 2).map(|_| quote!(super::));
+    let catalog = quote!(#(#root)* catalog);
+    let locale_subkeys: BTreeMap<_, _> = locales
+        .iter()
+        .map(|(name, string_keys)| {
+            let mut keys = BTreeMap::new();
+            string_keys
+                .keys
+                .iter()
+                .filter_map(|(k, v)| match v {
+                    StringItem::Subkey(v) => Some((k, v)),
+                    _ => None,
+                })
+                .for_each(|(k, v)| match base_locale_strings.keys.get(k) {
+                    Some(StringItem::Subkey(_)) => {
+                        keys.insert(k.clone(), v);
+                    }
+                    Some(_) => warn(format!(
+                        "Default locale has incompatible non-subkey key {:?} from locale {}!",
+                        k, name
+                    )),
+                    None => warn(format!(
+                        "Default locale is missing key {:?} from locale {}!",
+                        k, name
+                    )),
+                });
+            (name, keys)
+        })
+        .collect();
+    let locale_subkeys = base_locale_strings
+        .keys
+        .iter()
+        .filter_map(|(k, v)| match v {
+            StringItem::Subkey(v) => Some((k, v)),
+            _ => None,
+        })
+        .map(|(k, v)| {
+            let locales: BTreeMap<&str, StringSet> = locale_subkeys
+                .iter()
+                .map(|(l, m)| {
+                    (
+                        **l,
+                        StringSet {
+                            name: l,
+                            keys: m.get(k).map(|s| (*s).clone()).unwrap_or_else(BTreeMap::new),
+                            fallback: locales[*l].fallback,
+                            feature: locales[*l].feature,
+                        },
+                    )
+                })
+                .collect();
+            let v = generate_keys(
+                &format!("{}{}.", prefix, k),
+                locales.iter().map(|(k, v)| (*k, v)).collect(),
+                &StringSet {
+                    name: base_locale_strings.name,
+                    keys: v.clone(),
+                    fallback: None,
+                    feature: None,
+                },
+                type_ident(k),
+                key_infos,
+                compact.as_deref_mut(),
+            )?;
+            let k = module_ident(k);
+            Ok(quote! {
+                pub mod #k {
+                    #v
+                }
+            })
+        })
+        .collect::<Result<Vec<_>, Error>>()?;
+
+    let locale_ast: BTreeMap<_, _> = locales
+        .iter()
+        .map(|(name, l)| {
+            let mut keys = BTreeMap::new();
+            l.keys
+                .iter()
+                .filter_map(|(k, v)| match v {
+                    StringItem::String(v) => Some((k, v)),
+                    StringItem::Subkey(_) | StringItem::Metadata(_) => None,
+                })
+                .for_each(|(k, v)| {
+                    keys.insert(
+                        k.clone(),
+                        parse::<String>(v).map_err(|(message, span)| Error::ParseKeyErr {
+                            locale: l.name.to_string(),
+                            key: k.to_string(),
+                            src: v.to_string(),
+                            message,
+                            span,
+                        }),
+                    );
+                });
+            (name, keys)
+        })
+        .collect();
+
+    let string_keys = base_locale_strings
+        .keys
+        .iter()
+        .filter(|(k, v)| {
+            match v{
+                StringItem::String(_) => (),
+                StringItem::Subkey(_) | StringItem::Metadata(_) => return false,
+            }
+            locale_ast.iter().all(|(_l, v)| match v.get(*k) {
+                Some(s) => matches!(s, Ok(r) if r.iter().all(|t| matches!(t, AstToken::Content { value: _ }))),
+                None => true,
+            })
+        })
+        .map(|(k, _)| k)
+        .collect::<Vec<_>>();
+
+    // Each argument's type, unified across locales, and the locale that required it.
+    let mut dyn_keys: BTreeMap<_, BTreeMap<_, (ArgType, &str)>> = BTreeMap::new();
+    for (locale, asts) in locale_ast.iter() {
+        for (k, ast) in asts.iter().filter(|(k, _)| !string_keys.contains(k)) {
+            if base_locale_strings.keys.contains_key(k) {
+                if let Ok(ast) = ast {
+                    let args = dyn_keys.entry(k).or_default();
+                    for (arg, arg_types) in ast.get_args() {
+                        let mut arg_types: Vec<_> = arg_types.into_iter().collect();
+                        arg_types.sort();
+                        for arg_type in arg_types {
+                            let (current, current_locale) =
+                                args.entry(arg).or_insert((arg_type, locale));
+                            match current.unify(arg_type) {
+                                Some(unified) if unified == *current => {}
+                                Some(unified) => {
+                                    *current = unified;
+                                    *current_locale = locale;
+                                }
+                                None => {
+                                    return Err(Error::ArgTypeConflict {
+                                        key: format!("{}{}", prefix, k),
+                                        arg: arg.to_string(),
+                                        first_locale: current_locale.to_string(),
+                                        first_type: *current,
+                                        second_locale: locale.to_string(),
+                                        second_type: arg_type,
+                                    })
+                                }
+                            }
+                        }
+                    }
+                }
+            } else {
+                // Default locale is missing this key!
+                warn(format!(
+                    "Default locale is missing key {:?} from locale {}!",
+                    k, locale
+                ))
+            }
+        }
+    }
+    let dyn_keys = dyn_keys
+        .into_iter()
+        .map(|(k, args)| {
+            let mut args = args
+                .into_iter()
+                .map(|(arg, (arg_type, _))| {
+                    let arg_type = Arg {
+                        arg_type,
+                        select_type: None,
+                    };
+                    (arg, arg_type)
+                })
+                .collect::<BTreeMap<_, _>>();
+            let declared = base_locale_strings.metadata(k).map(|m| &m.args);
+            for (name, arg_metadata) in declared.into_iter().flatten() {
+                let invalid = |message: &str| Error::InvalidArgMetadata {
+                    key: format!("{}{}", prefix, k),
+                    arg: name.clone(),
+                    message: message.to_owned(),
+                };
+                let arg = args
+                    .get_mut(name)
+                    .ok_or_else(|| invalid("not an argument of the message"))?;
+                if let Some(select_type) = &arg_metadata.select_type {
+                    if arg.arg_type != ArgType::SelectArg {
+                        return Err(invalid("only select arguments can have a type"));
+                    }
+                    syn::parse_str::<syn::Type>(select_type)
+                        .map_err(|err| invalid(&format!("invalid type: {}", err)))?;
+                    arg.select_type = Some(select_type.clone());
+                }
+            }
+            Ok((k, args))
+        })
+        .collect::<Result<BTreeMap<_, _>, Error>>()?;
+    for key in string_keys.iter() {
+        let declared = base_locale_strings.metadata(key).map(|m| &m.args);
+        if let Some(name) = declared.and_then(|args| args.keys().next()) {
+            return Err(Error::InvalidArgMetadata {
+                key: format!("{}{}", prefix, key),
+                arg: name.clone(),
+                message: "not an argument of the message".to_owned(),
+            });
+        }
+    }
+
+    let compact_messages = compact.as_deref_mut().map(|tables| {
+        compact_messages(
+            prefix,
+            tables,
+            &quote!(super::#catalog),
+            &locales,
+            &locale_ast,
+            &string_keys,
+            &dyn_keys,
+        )
+    });
+
+    let builder_defs: Vec<TokenStream> = dyn_keys
+        .iter()
+        .map(|(key, args)| {
+            let ident = mangle(key);
+            let type_params = args
+              .keys()
+              .map(|arg| Ident::new(&format!("__{}", arg), Span::call_site()));
+            let concrete_types: Vec<_> = args
+              .values()
+              .map(|arg| arg_type_tokens(arg, quote!()))
+              .collect();
+
+            let field_names: Vec<_> = args.keys().map(|arg| {
+                Ident::new(&format!("arg_{}", arg), Span::call_site())
+            }).collect();
+            let fields = args.keys().map(|arg| {
+                let key = Ident::new(&format!("arg_{}", arg), Span::call_site());
+                let type_param = Ident::new(&format!("__{}", arg), Span::call_site());
+                quote!(#key: #type_param)
+            });
+            let default_type_params = args.iter().map(|_| quote!(EmptyValue));
+            let default_fields = field_names.iter().map(|key| {
+                quote!(#key: EmptyValue)
+            });
+            let formatter_args: Vec<_> = field_names.iter().map(|key| {
+                quote!(self.#key)
+            }).collect();
+            let formatter_type = quote!(&'a for<'x> fn(&'x mut dyn mf1::Formattable, #(#concrete_types,)*) -> std::fmt::Result);
+            // The fields which say how the message is formatted: its formatter,
+            // or in the compact codegen its locale.
+            let (handle_defs, handle_fields, new_param, formatter, formatter_fn) = match &compact_messages {
+                Some(compact) => {
+                    let formatter_fn = quote::format_ident!("__mf1_format_{}", ident);
+                    (
+                        quote!(locale: u16, _lifetime: std::marker::PhantomData<&'a ()>,),
+                        vec![quote::format_ident!("locale"), quote::format_ident!("_lifetime")],
+                        quote!(locale: u16),
+                        quote!(#formatter_fn(out, self.locale, #(#formatter_args,)*)),
+                        compact.formatters[key.as_ref()].clone(),
+                    )
+                }
+                None => (
+                    quote!(formatter: #formatter_type,),
+                    vec![quote::format_ident!("formatter")],
+                    quote!(formatter: #formatter_type),
+                    quote!((self.formatter)(out, #(#formatter_args,)*)),
+                    quote!(),
+                ),
+            };
+            let new_fields = match &compact_messages {
+                Some(_) => quote!(locale, _lifetime: std::marker::PhantomData,),
+                None => quote!(formatter,),
+            };
+            fn gen_setter(ident: &syn::Ident, handle_fields: &[Ident], field: &syn::Ident, arg: &Arg, left_fields: &[Ident], right_fields: &[Ident]) -> proc_macro2::TokenStream {
+                let restructure_others = left_fields.iter().chain(right_fields.iter());
+                let other_fields = restructure_others.clone();
+
+                let left_type_params: Vec<_> = left_fields.iter().map(|arg| Ident::new(&format!("__{}", arg), Span::call_site())).collect();
+                let right_type_params: Vec<_> = right_fields.iter().map(|arg| Ident::new(&format!("__{}", arg), Span::call_site())).collect();
+
+                let all_type_params = left_fields.iter().chain(right_fields.iter()).map(|arg| Ident::new(&format!("__{}", arg), Span::call_site()));
+                let field_type = arg_type_tokens(arg, quote!('b));
+
+                quote! {
+
+                    impl<'a, #(#all_type_params,)*> #ident<'a, #(#left_type_params,)*EmptyValue,#(#right_type_params,)*> {
+                        pub fn #field<'b>(self, #field: #field_type) -> #ident<'a,#(#left_type_params,)*#field_type,#(#right_type_params,)*> {
+                            let #ident { #(#handle_fields,)* #(#other_fields,)* .. } = self;
+                            #ident { #(#handle_fields,)* #field, #(#restructure_others,)*  }
+                        }
+                    }
+                }
+            }
+    fn split_at<T>(slice: &[T], i: usize) -> (&[T], &T, &[T]) {
+        let (left, rest) = slice.split_at(i);
+        let (mid, right) = rest.split_first().unwrap();
+        (left, mid, right)
+    }
+            let arg_names = args.keys();
+            let dynamic_args = args.iter().map(|(name, arg)| dynamic_arg_tokens(name, arg));
+            let dynamic_formatter = match &compact_messages {
+                Some(_) => {
+                    let formatter_fn = quote::format_ident!("__mf1_format_{}", ident);
+                    quote!(#formatter_fn(out, self.locale, #(#dynamic_args,)*))
+                }
+                None => quote!((self.formatter)(out, #(#dynamic_args,)*)),
+            };
+            let empty_type_params = args.iter().map(|_| quote!(EmptyValue));
+            let setters = args.values().enumerate()
+            .map(|(i, arg)| {
+                let (left_fields, field, right_fields) = split_at(&field_names, i);
+                gen_setter(&ident, &handle_fields, field, arg, left_fields, right_fields)
+            });
+            let docs = key_docs(key, base_locale_strings, &locales, Some(args));
+            quote! {
+                #docs
+                #[allow(non_camel_case_types, non_snake_case)]
+                // Function pointers are compared in the automatically derived PartialEq, Eq, and Hash implementations.
+                // While comparing function pointers is generally unreliable (identical functions can have different addresses
+                // across compilation units), it's acceptable here as we only care about the *exact* same function.
+                #[allow(unpredictable_function_pointer_comparisons)]
+                #[derive(Copy, Clone, Debug, Hash, PartialEq, Eq)]
+                // #[derive(Clone, Copy, Hash, PartialEq, Eq, Debug)]
+                pub struct #ident<'a, #(#type_params,)*> {
+                    #handle_defs
+                    #(#fields,)*
+                }
+
+                impl<'a> #ident<'a, #(#default_type_params,)*> {
+                    pub const fn new(#new_param) -> Self {
+                        Self {
+                            #new_fields
+                            #(#default_fields,)*
+                        }
+                    }
+                }
+                #formatter_fn
+                #(#setters)*
+                impl<'a> mf1::BuildStr for #ident<'a, #(#concrete_types,)*> {
+                    #[inline]
+                    fn build_string(self) -> std::borrow::Cow<'static, str> {
+                        std::borrow::Cow::Owned(format!("{}", self))
+                    }
+                }
+                impl<'a> mf1::Message for #ident<'a, #(#concrete_types,)*> {
+                    fn write_to(&self, out: &mut dyn mf1::Formattable) -> std::fmt::Result {
+                        #formatter
+                    }
+                }
+                impl<'a> std::fmt::Display for #ident<'a, #(#concrete_types,)*> {
+                    fn fmt(&self, out: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                        #formatter
+                    }
+                }
+                impl mf1::DynamicMessage for #ident<'static, #(#empty_type_params,)*> {
+                    fn arg_names(&self) -> &'static [&'static str] {
+                        &[#(#arg_names,)*]
+                    }
+
+                    fn write_dynamic(&self, out: &mut dyn mf1::Formattable, args: &dyn mf1::Args) -> Result<(), mf1::FormatError> {
+                        Ok(#dynamic_formatter?)
+                    }
+                }
+
+            }
+        })
+        .collect::<Vec<_>>();
+
+    let subkey_field_defs = base_locale_strings
+        .keys
+        .iter()
+        .filter_map(|(k, v)| match v {
+            StringItem::Subkey(_) => {
+                let mod_name = module_ident(k);
+                let type_name = type_ident(k);
+                let doc = doc_attr(base_locale_strings.metadata(k));
+                let k = mangle(k);
+                Some(quote!(#doc pub #k: subkeys::#mod_name::#type_name))
+            }
+            _ => None,
+        });
+    let dyn_field_defs = dyn_keys.iter().map(|(key, args)| {
+        let doc = key_docs(key, base_locale_strings, &locales, Some(args));
+        let key = mangle(key);
+        let type_params = args.iter().map(|_| quote!(builders::EmptyValue));
+        quote!(#doc pub #key: builders::#key<'static, #(#type_params,)*>)
+    });
+
+    let string_field_defs = string_keys.iter().map(|key| {
+        let doc = key_docs(key, base_locale_strings, &locales, None);
+        let field_type = match &compact_messages {
+            Some(compact) => {
+                let index = compact.keys[key.as_ref()];
+                quote!(#catalog::Text<#index>)
+            }
+            None => quote!(&'static str),
+        };
+        let key = mangle(key);
+        quote!(#doc pub #key: #field_type)
+    });
+
+    let keys_type = quote! {
+        #[doc(hidden)]
+        pub mod builders {
+            #[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+            pub struct EmptyValue;
+            #(#builder_defs)*
+        }
+
+        #[derive(Copy, Clone, Debug, Hash, PartialEq, Eq)]
+        #[allow(non_camel_case_types, non_snake_case)]
+        pub struct #i18n_keys_ident {
+            #(#string_field_defs,)*
+            #(#dyn_field_defs,)*
+            #(#subkey_field_defs,)*
+        }
+    };
+
+    for key in base_locale_strings.keys.keys() {
+        let args = match dyn_keys.get(key) {
+            Some(args) => Some(args),
+            None if string_keys.contains(&key) => None,
+            None => continue,
+        };
+        let path = format!("{}{}", prefix, key);
+        let arg_infos = args.into_iter().flatten().map(|(name, arg)| {
+            let arg_type = match arg.arg_type {
+                ArgType::OrdinalArg => quote!(OrdinalArg),
+                ArgType::PlainArg => quote!(PlainArg),
+                ArgType::SelectArg => quote!(SelectArg),
+                ArgType::FunctionArg => quote!(FunctionArg),
+            };
+            let select_type = match &arg.select_type {
+                Some(select_type) => quote!(Some(#select_type)),
+                None => quote!(None),
+            };
+            quote! {
+                mf1::ArgInfo {
+                    name: #name,
+                    arg_type: mf1::ArgType::#arg_type,
+                    select_type: #select_type,
+                }
+            }
+        });
+        // Matches the fallbacks of the locale values below. A locale which is
+        // compiled out by its feature takes every message from its fallback.
+        let (translated, untranslated): (Vec<_>, Vec<_>) =
+            locales.iter().partition(|(name, l)| match args {
+                Some(_) => matches!(locale_ast[name].get(key), Some(Ok(_))),
+                None => matches!(l.keys.get(key), Some(StringItem::String(_))),
+            });
+        let fallbacks = untranslated
+            .iter()
+            .map(|(_, l)| (quote!(), l.ident()))
+            .chain(translated.iter().filter_map(|(_, l)| {
+                let feature = l.feature?;
+                Some((quote!(#[cfg(not(feature = #feature))]), l.ident()))
+            }))
+            .map(|(cfg, ident)| quote!(#cfg Locale::#ident));
+        let translated = translated.iter().map(|(_, l)| {
+            let ident = l.ident();
+            match l.feature {
+                Some(feature) => quote!(#[cfg(feature = #feature)] Locale::#ident),
+                None => quote!(Locale::#ident),
+            }
+        });
+        key_infos.insert(
+            path.clone(),
+            quote! {
+                mf1::KeyInfo {
+                    path: #path,
+                    args: &[#(#arg_infos,)*],
+                    translated: &[#(#translated,)*],
+                    fallbacks: &[#(#fallbacks,)*],
+                }
+            },
+        );
+    }
+
+    let lookup = {
+        let message_keys: Vec<_> = base_locale_strings
+            .keys
+            .keys()
+            .filter(|k| string_keys.contains(k) || dyn_keys.contains_key(k))
+            .collect();
+        let message_idents: Vec<_> = message_keys.iter().map(|k| mangle(k)).collect();
+        let message_paths = message_keys.iter().map(|k| format!("{}{}", prefix, k));
+        let message_count = message_keys.len();
+        let subkeys: Vec<_> = base_locale_strings
+            .keys
+            .iter()
+            .filter(|(_, v)| matches!(v, StringItem::Subkey(_)))
+            .map(|(k, _)| k)
+            .collect();
+        let subkey_idents: Vec<_> = subkeys.iter().map(|k| mangle(k)).collect();
+        let subkey_prefixes = subkeys.iter().map(|k| format!("{}.", k));
+        let match_messages = (!message_keys.is_empty()).then(|| {
+            quote! {
+                match path {
+                    #(#message_keys => return Some(mf1::MessageRef::new(&self.#message_idents)),)*
+                    _ => {}
+                }
+            }
+        });
+        quote! {
+            impl #i18n_keys_ident {
+                /// Looks up a message by its `.`-separated path relative to these
+                /// keys, such as `"nested.keys"`.
+                pub fn get(&self, path: &str) -> Option<mf1::MessageRef<'_>> {
+                    #match_messages
+                    #(
+                        if let Some(message) = path
+                            .strip_prefix(#subkey_prefixes)
+                            .and_then(|path| self.#subkey_idents.get(path))
+                        {
+                            return Some(message);
+                        }
+                    )*
+                    None
+                }
+
+                /// Iterates over the full paths of all messages and the
+                /// messages, with nested messages last.
+                pub fn iter(&self) -> impl Iterator<Item = (&'static str, mf1::MessageRef<'_>)> + '_ {
+                    let messages: [(&'static str, mf1::MessageRef<'_>); #message_count] = [
+                        #((#message_paths, mf1::MessageRef::new(&self.#message_idents)),)*
+                    ];
+                    messages.into_iter()#(.chain(self.#subkey_idents.iter()))*
+                }
+            }
+        }
+    };
+
+    let locale_values = locales.iter().map(|locale| {
+        let ident = locale.1.ident();
+        // Missing messages are taken from the fallback, whose own missing
+        // messages are taken from its fallback, up to the base locale.
+        let fallback_ident = locale.1.fallback.map_or_else(|| base_locale_ident.clone(), locale_ident);
+        let subkey_fields = base_locale_strings
+        .keys
+        .iter()
+        .filter_map(|(k, v)| match v {
+            StringItem::Subkey(_) => {
+                let mod_name = module_ident(k);
+                let k = mangle(k);
+            Some(quote!(#k: subkeys::#mod_name::#ident))
+            },
+            _ => None,
+        });
+        let string_fields = string_keys.iter().map(|key| {
+            let key_ident = mangle(key);
+            match locale.1.keys.get(*key) {
+                Some(StringItem::String(value)) => quote!(#key_ident: #value),
+                Some(StringItem::Subkey(_)) => unreachable!(),
+                _ => {
+                    quote!(#key_ident: #fallback_ident.#key_ident)
+                }
+            }
+        });
+        let formatter_fields = dyn_keys.iter().map(|(key, arg_types)| {
+            let key_ident = mangle(key);
+            match locale_ast.get(locale.0).unwrap().get(*key) {
+                Some(Ok(ast)) => {
+                    let args = arg_types
+                        .iter()
+                        .map(|(name, arg_type)| {
+                            let name = mangle(name);
+                            let arg_type = arg_type_tokens(arg_type, quote!());
+                            quote! {#name: #arg_type}
+                        });
+                    let message = Message::new(locale.1.name, format!("{}{}", prefix, key), arg_types);
+                    let items: Vec<_> = ast.iter().map(|t| gen_items(t, &message, false)).collect();
+                    let checks = message.checks.into_inner();
+                    quote!(#key_ident: builders::#key_ident::new(&(|fmt: &mut dyn mf1::Formattable, #(#args,)*| -> std::fmt::Result {
+                        #(#checks)*
+                        #(#items)*
+                        Ok(())
+                    } as _)))
+                },
+                _ => {
+                    quote!(#key_ident: #fallback_ident.#key_ident)
+                }
+            }
+        });
+
+
+        let value = quote! {
+            #[allow(non_upper_case_globals)]
+            pub(crate) const #ident: #i18n_keys_ident = #i18n_keys_ident {
+                #(#string_fields,)*
+                #(#formatter_fields,)*
+                #(#subkey_fields,)*
+            };
+        };
+        match locale.1.feature {
+            // A disabled locale uses the messages of its fallback, so none of
+            // its own are compiled.
+            Some(feature) => quote! {
+                #[cfg(feature = #feature)]
+                #value
+                #[cfg(not(feature = #feature))]
+                #[allow(non_upper_case_globals)]
+                pub(crate) const #ident: #i18n_keys_ident = #fallback_ident;
+            },
+            None => value,
+        }
+    });
+
+    let locale_static = match compact.as_deref() {
+        // Each locale's messages are handles to its string table, so only the
+        // top level has values for each locale.
+        Some(tables) => {
+            let string_values = string_keys.iter().map(|key| {
+                let key = mangle(key);
+                quote!(#key: #catalog::Text::new(locale))
+            });
+            let dyn_values = dyn_keys.keys().map(|key| {
+                let key = mangle(key);
+                quote!(#key: builders::#key::new(locale))
+            });
+            let subkey_values = base_locale_strings
+                .keys
+                .iter()
+                .filter(|(_, v)| matches!(v, StringItem::Subkey(_)))
+                .map(|(k, _)| {
+                    let mod_name = module_ident(k);
+                    let type_name = type_ident(k);
+                    let k = mangle(k);
+                    quote!(#k: subkeys::#mod_name::#type_name::new(locale))
+                });
+            let locale_consts = prefix.is_empty().then(|| {
+                let idents = locales.values().map(|l| l.ident());
+                let indices = locales.keys().map(|name| tables.locale_index(name) as u16);
+                quote! {
+                    #(
+                        #[allow(non_upper_case_globals)]
+                        pub(crate) const #idents: #i18n_keys_ident = #i18n_keys_ident::new(#indices);
+                    )*
+                }
+            });
+            quote! {
+                impl #i18n_keys_ident {
+                    #[doc(hidden)]
+                    pub const fn new(locale: u16) -> Self {
+                        Self {
+                            #(#string_values,)*
+                            #(#dyn_values,)*
+                            #(#subkey_values,)*
+                        }
+                    }
+                }
+                #locale_consts
+            }
+        }
+        None => quote! {
+            #(
+                #locale_values
+            )*
+        },
+    };
+    Ok(quote! {
+        #[doc(hidden)]
+        pub mod subkeys {
+            #(#locale_subkeys)*
+        }
+        #keys_type
+        #lookup
+        #locale_static
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn string_set<'a>(name: &'a str, src: &str) -> StringSet<'a> {
+        let keys = deserialize_keys(&mut serde_json::Deserializer::from_str(src)).unwrap();
+        StringSet {
+            name,
+            keys,
+            fallback: None,
+            feature: None,
+        }
+    }
+
+    #[test]
+    fn deterministic_output() {
+        let generate = || {
+            let en = string_set(
+                "en",
+                r#"{
+                    "a": "A", "b": "{x} and {y}", "c": "{z, select, one {1} other {2}}",
+                    "d": {"e": "E {w}", "f": "F", "g": {"h": "H"}}, "i": "I {v}"
+                }"#,
+            );
+            let es = string_set("es", r#"{"b": "{y} y {x}", "d": {"f": "F"}, "i": "I"}"#);
+            let fr = string_set("fr", r#"{"a": "A", "d": {"e": "E {w}"}}"#);
+            let locales = [("en", &en), ("es", &es), ("fr", &fr)]
+                .into_iter()
+                .collect();
+            generate_keys(
+                "",
+                locales,
+                &en,
+                quote::format_ident!("Mf1Keys"),
+                &mut BTreeMap::new(),
+                None,
+            )
+            .unwrap()
+            .to_string()
+        };
+        let first = generate();
+        for _ in 0..16 {
+            assert_eq!(generate(), first);
+        }
+    }
+
+    #[test]
+    fn compact_shapes() {
+        let en = string_set(
+            "en",
+            r#"{"a": "{x} and {y}", "b": " and ", "c": {"d": "D"}}"#,
+        );
+        let es = string_set("es", r#"{"a": "{x} y {y}", "c": {"d": "D"}}"#);
+        let fr = string_set("fr", r#"{"a": "{y} et {x}", "b": " et "}"#);
+        let locales = [("en", &en), ("es", &es), ("fr", &fr)]
+            .into_iter()
+            .collect();
+        let mut tables = CompactTables::new(vec!["en".into(), "es".into(), "fr".into()]);
+        generate_keys(
+            "",
+            locales,
+            &en,
+            quote::format_ident!("Mf1Keys"),
+            &mut BTreeMap::new(),
+            Some(&mut tables),
+        )
+        .unwrap();
+        // `c.d` is generated first, then `b`, then `a`.
+        let [en, es, fr] = &tables.tables[..] else {
+            unreachable!()
+        };
+        assert_eq!(tables.key_count, 3);
+        assert_eq!(es.messages[&2].0, en.messages[&2].0);
+        assert_ne!(fr.messages[&2].0, en.messages[&2].0);
+        assert!(!fr.messages.contains_key(&0));
+        // Pieces of text are only stored once per locale.
+        assert_eq!(en.text, "D and ");
+        assert_eq!(en.spans, [(0, 1), (1, 6), (1, 6)]);
+    }
+
+    #[test]
+    fn namespace_files() {
+        let dir = std::env::temp_dir().join(format!("mf1-namespaces-{}", std::process::id()));
+        let write = |path: &str, src: &str| {
+            let path = dir.join(path);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, src).unwrap();
+        };
+        write(
+            "en.json",
+            r#"{"title": "Title", "settings": {"title": "Settings"}}"#,
+        );
+        write("en/settings/profile.json", r#"{"name": "Name"}"#);
+        write("en/settings.json", r#"{"email": "Email"}"#);
+        let en = StringSet::load("en", &dir, "en").unwrap();
+        assert_eq!(
+            en,
+            string_set(
+                "en",
+                r#"{
+                    "title": "Title",
+                    "settings": {"title": "Settings", "email": "Email", "profile": {"name": "Name"}}
+                }"#
+            )
+        );
+
+        write("en/settings/profile/name.json", r#"{"first": "First"}"#);
+        let err = StringSet::load("en", &dir, "en").unwrap_err();
+        std::fs::remove_dir_all(&dir).unwrap();
+        let Error::DuplicateKey { key, first, second } = err else {
+            panic!("{err}")
+        };
+        assert_eq!(key, "settings.profile.name");
+        assert_eq!(first, dir.join("en/settings/profile/name.json"));
+        assert_eq!(second, dir.join("en/settings/profile.json"));
+    }
+
+    #[test]
+    fn workspace_config() {
+        let dir = std::env::temp_dir().join(format!("mf1-workspace-{}", std::process::id()));
+        let member = dir.join("crates/app");
+        std::fs::create_dir_all(&member).unwrap();
+        std::fs::write(
+            dir.join("Cargo.toml"),
+            r#"
+                [workspace]
+                members = ["crates/*"]
+
+                [workspace.metadata.mf1]
+                locales = ["en", "es"]
+                locales_dir = "i18n"
+                locale_features = true
+            "#,
+        )
+        .unwrap();
+        let read = |manifest: &str| {
+            let manifest = manifest.parse::<toml::Table>().unwrap();
+            read_config(&member, &manifest, &mut Vec::new()).unwrap()
+        };
+
+        let config = read("[package]\nname = \"app\"");
+        assert_eq!(config.locales, ["en", "es"]);
+        assert_eq!(
+            config.locales_dir,
+            Some(dir.join("i18n").to_string_lossy().into())
+        );
+        assert!(config.locale_features);
+
+        let config = read(
+            r#"
+                [package.metadata.mf1]
+                locales = ["en", "fr"]
+                locales_dir = "locales"
+            "#,
+        );
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(config.locales, ["en", "fr"]);
+        assert_eq!(
+            config.locales_dir,
+            Some(member.join("locales").to_string_lossy().into())
+        );
+        assert!(config.locale_features);
+    }
+
+    #[test]
+    fn canonical_locales() {
+        let canonicalizer = LocaleCanonicalizer::new();
+        let resolve = |names: &[&str]| {
+            let names: Vec<_> = names.iter().map(|n| n.to_string()).collect();
+            resolve_locales(&names, &canonicalizer)
+        };
+        let entries = resolve(&["en_UK", "pt-br", "zh-hant-tw"]).unwrap();
+        let tags: Vec<_> = entries.iter().map(|e| e.tag.as_str()).collect();
+        assert_eq!(tags, ["en-GB", "pt-BR", "zh-Hant-TW"]);
+        assert_eq!(entries[0].file, "en_UK");
+
+        let err = resolve(&["pt-BR", "pt_br"]).err().unwrap();
+        assert_eq!(
+            err.to_string(),
+            "Locales \"pt-BR\" and \"pt_br\" are both pt-BR"
+        );
+        assert!(matches!(resolve(&["e"]), Err(Error::InvalidLocale { .. })));
+    }
+
+    #[test]
+    fn fallback_chains() {
+        let canonicalizer = LocaleCanonicalizer::new();
+        let resolve = |names: &[&str], table: &[(&str, &str)]| {
+            let names: Vec<_> = names.iter().map(|n| n.to_string()).collect();
+            let entries = resolve_locales(&names, &canonicalizer).unwrap();
+            let table = table
+                .iter()
+                .map(|(l, f)| (l.to_string(), f.to_string()))
+                .collect();
+            resolve_fallbacks(&entries, "en", &table, &canonicalizer)
+        };
+        let fallbacks = resolve(
+            &[
+                "en",
+                "en-GB",
+                "es",
+                "es-419",
+                "pt",
+                "pt-BR",
+                "zh-Hant-TW",
+                "zh-Hant",
+                "gl",
+            ],
+            &[("gl", "pt")],
+        )
+        .unwrap();
+        let fallbacks: Vec<_> = fallbacks.iter().map(|(l, f)| format!("{l}->{f}")).collect();
+        assert_eq!(
+            fallbacks,
+            [
+                "en-GB->en",
+                "es->en",
+                "es-419->es",
+                "gl->pt",
+                "pt->en",
+                "pt-BR->pt",
+                "zh-Hant->en",
+                "zh-Hant-TW->zh-Hant"
+            ]
+        );
+
+        let err = resolve(&["en", "es", "es-419"], &[("es", "es_419")]).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Invalid fallback for locale es: the fallbacks form a cycle: es -> es-419 -> es"
+        );
+        let err = resolve(&["en", "es"], &[("es", "fr")]).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Invalid fallback for locale es: fr is not a listed locale"
+        );
+    }
+
+    #[test]
+    fn key_docs_output() {
+        let en = string_set(
+            "en",
+            r#"{"items": "{name} has {count, plural, one {# item} other {# items}}",
+                "@items": {"description": "The items in a cart."}}"#,
+        );
+        let es = string_set("es", r#"{"items": "{name} tiene {count} artículos"}"#);
+        let es_419 = StringSet {
+            fallback: Some("es"),
+            feature: Some("locale-es-419"),
+            ..string_set("es-419", "{}")
+        };
+        let locales = [("en", &en), ("es", &es), ("es-419", &es_419)]
+            .into_iter()
+            .collect();
+        let (name, count) = ("name".to_owned(), "count".to_owned());
+        let args = [
+            (
+                &name,
+                Arg {
+                    arg_type: ArgType::PlainArg,
+                    select_type: None,
+                },
+            ),
+            (
+                &count,
+                Arg {
+                    arg_type: ArgType::OrdinalArg,
+                    select_type: None,
+                },
+            ),
+        ]
+        .into_iter()
+        .collect();
+        let docs = key_docs("items", &en, &locales, Some(&args));
+        let file = syn::parse_quote!(#docs pub struct Items;);
+        expect_test::expect![[r#"
+            /// The items in a cart.
+            ///
+            /// Base locale `en`:
+            /// ```text
+            /// {name} has {count, plural, one {# item} other {# items}}
+            /// ```
+            ///
+            /// Translations:
+            /// - `es`: translated
+            /// - `es-419`: missing, falls back to `es` (only compiled with the `locale-es-419` feature)
+            ///
+            /// Arguments:
+            /// - `count`: `impl mf1::Number`
+            /// - `name`: `impl Display`
+            pub struct Items;
+        "#]]
+        .assert_eq(&prettyplease::unparse(&file));
+    }
+
+    /// Validates the metadata of `en` against `es`, with screenshots relative to
+    /// this crate.
+    fn validate(en: &str, es: &str) -> Result<(), Error> {
+        let en = string_set("en", en);
+        let es = string_set("es", es);
+        validate_metadata(
+            "",
+            &en.keys,
+            &[("en", Some(&en.keys)), ("es", Some(&es.keys))],
+            "en",
+            Path::new(env!("CARGO_MANIFEST_DIR")),
+        )
+    }
+
+    #[test]
+    fn orphan_metadata() {
+        let en = r#"{"a": "A", "@a": {}, "b": {"c": "C", "@d": {"description": "D"}}}"#;
+        let err = validate(en, "{}").unwrap_err();
+        let Error::OrphanMetadata { locale, key } = err else {
+            panic!("{err}")
+        };
+        assert_eq!((locale.as_str(), key.as_str()), ("en", "b.d"));
+    }
+
+    #[test]
+    fn max_length_exceeded() {
+        let en = r#"{"a": "Save {name}", "@a": {"maxLength": 8}}"#;
+        validate(en, r#"{"a": "Sauver {name}"}"#).unwrap();
+        let err = validate(en, r#"{"a": "Enregistrer {name}"}"#).unwrap_err();
+        let Error::MaxLengthExceeded {
+            locale,
+            key,
+            length,
+            max,
+        } = err
+        else {
+            panic!("{err}")
+        };
+        assert_eq!((locale.as_str(), key.as_str()), ("es", "a"));
+        assert_eq!((length, max), (12, 8));
+    }
+
+    #[test]
+    fn translated_untranslatable() {
+        let en = r#"{"brand": "Acme", "@brand": {"translate": false}}"#;
+        validate(en, "{}").unwrap();
+        validate(en, r#"{"brand": "Acme"}"#).unwrap();
+        let err = validate(en, r#"{"brand": "Acmé"}"#).unwrap_err();
+        let Error::TranslatedUntranslatable { locale, key } = err else {
+            panic!("{err}")
+        };
+        assert_eq!((locale.as_str(), key.as_str()), ("es", "brand"));
+    }
+
+    #[test]
+    fn missing_screenshot() {
+        validate(r#"{"a": "A", "@a": {"screenshots": ["Cargo.toml"]}}"#, "{}").unwrap();
+        let en = r#"{"a": {"b": "B", "@b": {"screenshots": ["Cargo.toml", "b.png"]}}}"#;
+        let err = validate(en, "{}").unwrap_err();
+        let Error::MissingScreenshot { key, path } = err else {
+            panic!("{err}")
+        };
+        assert_eq!((key.as_str(), path.as_path()), ("a.b", Path::new("b.png")));
+    }
+
+    #[test]
+    fn key_collisions() {
+        let check = |src: &str| check_idents("", &string_set("en", src).keys);
+        check(r#"{"sign-in": "A", "sign_out": "B", "@sign-in": {}, "type": "C"}"#).unwrap();
+
+        let err = check(r#"{"sign-in": "A", "sign_in": "B"}"#).unwrap_err();
+        let Error::KeyCollision {
+            first,
+            second,
+            ident,
+        } = err
+        else {
+            panic!("{err}")
+        };
+        assert_eq!((first.as_str(), second.as_str()), ("sign-in", "sign_in"));
+        assert_eq!(ident, "sign_in");
+
+        let err = check(r#"{"a": {"self": "A", "self_": "B"}}"#).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Keys \"a.self\" and \"a.self_\" both map to the identifier `self_`"
+        );
+        // Subkeys also collide through their generated module and struct.
+        let err = check(r#"{"my-keys": {"a": "A"}, "my_keys": {"b": "B"}}"#).unwrap_err();
+        assert!(matches!(err, Error::KeyCollision { .. }), "{err}");
+    }
+
+    #[test]
+    fn arg_type_conflict() {
+        let en = string_set("en", r#"{"a": "{x, select, one {1} other {2}}"}"#);
+        let es = string_set("es", r#"{"a": "{x} {x, plural, one {1} other {2}}"}"#);
+        let locales = [("en", &en), ("es", &es)].into_iter().collect();
+        let err = generate_keys(
+            "",
+            locales,
+            &en,
+            quote::format_ident!("Mf1Keys"),
+            &mut BTreeMap::new(),
+            None,
+        )
+        .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Argument \"x\" of key a is used as SelectArg in en, but as OrdinalArg in es"
+        );
+    }
+}
@@ -0,0 +1,95 @@
+//! The input of the message macros of `mf1`, such as
+//! `t_l_string!(locale, nested.key, name = value)`.
+//!
+//! The grammar is shared by the macros and by [`crate::key_usage`], which
+//! finds the keys used by a crate.
+
+use syn::{ext::IdentExt, parse_quote, token, Expr, Ident};
+
+use crate::ident::{parse_key_path, KeySegment};
+
+/// The input of `t_l_string!` and `t_l_display!`, or of `t_string!` and
+/// `t_display!` with the current locale as its context.
+pub struct ParsedInput {
+    pub context: Expr,
+    pub keys: Vec<KeySegment>,
+    pub interpolations: Option<Vec<InterpolatedValue>>,
+}
+
+impl syn::parse::Parse for ParsedInput {
+    fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+        let context = input.parse()?;
+        input.parse::<token::Comma>()?;
+        ParsedInput::parse_with_context(context, input)
+    }
+}
+
+impl ParsedInput {
+    /// Parses the input of the ambient macros, which take no locale and use the
+    /// current locale of the thread instead.
+    pub fn parse_ambient(input: syn::parse::ParseStream) -> syn::Result<Self> {
+        ParsedInput::parse_with_context(parse_quote!(Locale::current()), input)
+    }
+
+    fn parse_with_context(context: Expr, input: syn::parse::ParseStream) -> syn::Result<Self> {
+        let keys = parse_key_path(input)?;
+        let interpolations = match input.parse::<token::Comma>() {
+            Ok(_) => {
+                let interpolations = input
+                    .parse_terminated(
+                        <InterpolatedValue as syn::parse::Parse>::parse,
+                        token::Comma,
+                    )?
+                    .into_iter()
+                    .collect();
+                Some(interpolations)
+            }
+            Err(_) if input.is_empty() => None,
+            Err(err) => return Err(err),
+        };
+        Ok(ParsedInput {
+            context,
+            keys,
+            interpolations,
+        })
+    }
+}
+
+/// An argument of a message, given as `name` for a variable of the same
+/// name, or as `name = value`.
+pub enum InterpolatedValue {
+    Var(Ident),
+    AssignedVar { key: Ident, value: Box<Expr> },
+}
+impl syn::parse::Parse for InterpolatedValue {
+    fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+        let key = Ident::parse_any(input)?.unraw();
+        let value = if input.peek(syn::Token![=]) {
+            input.parse::<syn::Token![=]>()?;
+            let value = Box::new(input.parse()?);
+            InterpolatedValue::AssignedVar { key, value }
+        } else {
+            InterpolatedValue::Var(key)
+        };
+        Ok(value)
+    }
+}
+/// The input of `t_l_write!` and `t_write!`, which starts with the writer.
+pub struct WriteInput {
+    pub writer: Expr,
+    pub input: ParsedInput,
+}
+
+impl WriteInput {
+    /// Parses the input, where `ambient` is whether it has no locale argument.
+    pub fn parse(input: syn::parse::ParseStream, ambient: bool) -> syn::Result<Self> {
+        let writer = input.parse()?;
+        input.parse::<token::Comma>()?;
+        let input = if ambient {
+            ParsedInput::parse_ambient(input)?
+        } else {
+            input.parse()?
+        };
+        Ok(WriteInput { writer, input })
+    }
+}
@@ -0,0 +1,158 @@
+//! The `convert` command.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use mf1_build::CatalogConfig;
+use mf1_convert::{android, apple, Catalog};
+
+use crate::{find_locale, Error};
+
+/// A format `convert` reads and writes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    /// The JSON format read by `load_locales!`.
+    Json,
+    /// An Android `strings.xml` file.
+    Android,
+    /// An Apple `.strings` file, and the `.stringsdict` file next to it for
+    /// messages with plurals.
+    Apple,
+}
+
+impl Format {
+    /// The format given by name, or else the format of a file by its extension.
+    pub fn for_file(name: Option<&String>, path: &Path) -> Result<Format, Error> {
+        let name = match name {
+            Some(name) => name.as_str(),
+            None => match path.extension().and_then(|ext| ext.to_str()) {
+                Some("json") => "json",
+                Some("xml") => "android",
+                Some("strings" | "stringsdict") => "apple",
+                _ => return Err(Error::UnknownFormat(path.to_owned())),
+            },
+        };
+        Ok(match name {
+            "json" => Format::Json,
+            "android" => Format::Android,
+            "apple" => Format::Apple,
+            _ => unreachable!("formats are checked by clap"),
+        })
+    }
+}
+
+/// The `.strings` and `.stringsdict` files of an Apple catalog.
+fn apple_files(path: &Path) -> (PathBuf, PathBuf) {
+    (
+        path.with_extension("strings"),
+        path.with_extension("stringsdict"),
+    )
+}
+
+fn read_file(path: &Path) -> Result<String, Error> {
+    std::fs::read_to_string(path).map_err(|err| Error::Read {
+        path: path.to_owned(),
+        err,
+    })
+}
+
+fn write_file(path: &Path, contents: &str) -> Result<(), Error> {
+    std::fs::write(path, contents).map_err(|err| Error::Write {
+        path: path.to_owned(),
+        err,
+    })
+}
+
+/// Reads a catalog from a file.
+pub fn read(path: &Path, format: Option<&String>) -> Result<Catalog, Error> {
+    Ok(match Format::for_file(format, path)? {
+        Format::Json => Catalog::from_json(&read_file(path)?)?,
+        Format::Android => android::from_strings_xml(&read_file(path)?)?,
+        Format::Apple => {
+            let (strings, stringsdict) = apple_files(path);
+            let mut catalog = Catalog::default();
+            if strings.is_file() || !stringsdict.is_file() {
+                catalog.extend(apple::from_strings(&read_file(&strings)?)?);
+            }
+            if stringsdict.is_file() {
+                catalog.extend(apple::from_stringsdict(&read_file(&stringsdict)?)?);
+            }
+            catalog
+        }
+    })
+}
+
+/// Reads a locale of the catalog from all of its files.
+pub fn read_locale(catalog: &CatalogConfig, locale: &str) -> Result<Catalog, Error> {
+    let entry = find_locale(catalog, locale)?;
+    let namespace_dir = catalog.locales_dir.join(&entry.file);
+    let mut messages = Catalog::default();
+    for path in catalog.files(entry)? {
+        let file = Catalog::from_json(&read_file(&path)?)?;
+        // `en/settings/profile.json` has the keys under `settings.profile.`.
+        let prefix = match path.with_extension("").strip_prefix(&namespace_dir) {
+            Ok(namespace) => namespace
+                .iter()
+                .map(|segment| format!("{}.", segment.to_string_lossy()))
+                .collect(),
+            Err(_) => String::new(),
+        };
+        messages.extend(Catalog {
+            messages: prefixed(&prefix, file.messages),
+            metadata: prefixed(&prefix, file.metadata),
+        });
+    }
+    Ok(messages)
+}
+
+fn prefixed<T>(prefix: &str, entries: BTreeMap<String, T>) -> BTreeMap<String, T> {
+    entries
+        .into_iter()
+        .map(|(path, value)| (format!("{}{}", prefix, path), value))
+        .collect()
+}
+
+/// Writes a catalog to a file. Apple catalogs only get a `.stringsdict` file
+/// if they have messages with plurals.
+pub fn write(catalog: &Catalog, path: &Path, format: Format) -> Result<(), Error> {
+    match format {
+        Format::Json => write_file(path, &catalog.to_json()?),
+        Format::Android => write_file(path, &android::to_strings_xml(catalog)?),
+        Format::Apple => {
+            let (strings, stringsdict) = apple_files(path);
+            write_file(&strings, &apple::to_strings(catalog)?)?;
+            let plurals = apple::to_stringsdict(catalog)?;
+            if plurals != apple::to_stringsdict(&Catalog::default())? {
+                write_file(&stringsdict, &plurals)?;
+            }
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formats_by_extension() {
+        let format = |name: Option<&str>, path: &str| {
+            Format::for_file(name.map(str::to_owned).as_ref(), Path::new(path)).ok()
+        };
+        assert_eq!(format(None, "locales/en.json"), Some(Format::Json));
+        assert_eq!(
+            format(None, "res/values/strings.xml"),
+            Some(Format::Android)
+        );
+        assert_eq!(
+            format(None, "en.lproj/Localizable.strings"),
+            Some(Format::Apple)
+        );
+        assert_eq!(format(None, "Localizable.stringsdict"), Some(Format::Apple));
+        assert_eq!(
+            format(Some("android"), "strings.txt"),
+            Some(Format::Android)
+        );
+        assert_eq!(format(None, "strings.txt"), None);
+    }
+}
@@ -0,0 +1,159 @@
+//! The `fmt` command.
+//!
+//! Locale files are written with their keys sorted, each `@key` entry right
+//! after the key it describes, and with four spaces of indentation. Messages
+//! are left as they are: reformatting them with `mf1_parser::to_source` would
+//! also escape every apostrophe, which is harder for translators to read.
+
+use std::fmt::Write;
+use std::path::PathBuf;
+
+use mf1_build::CatalogConfig;
+use serde_json::{Map, Value};
+
+use crate::Error;
+
+const INDENT: &str = "    ";
+
+/// Formats every locale file of the catalog, or with `check`, only lists
+/// the files that aren't formatted. Returns whether every file was formatted.
+pub fn fmt(catalog: &CatalogConfig, check: bool) -> Result<bool, Error> {
+    let mut unformatted: Vec<PathBuf> = Vec::new();
+    for locale in &catalog.locales {
+        for path in catalog.files(locale)? {
+            let src = std::fs::read_to_string(&path).map_err(|err| Error::Read {
+                path: path.clone(),
+                err,
+            })?;
+            let formatted = format_file(&src).map_err(|err| Error::Json {
+                path: path.clone(),
+                err,
+            })?;
+            if formatted == src {
+                continue;
+            }
+            if !check {
+                std::fs::write(&path, formatted).map_err(|err| Error::Write {
+                    path: path.clone(),
+                    err,
+                })?;
+            }
+            unformatted.push(path);
+        }
+    }
+    for path in &unformatted {
+        if check {
+            println!("Not formatted: {}", path.display());
+        } else {
+            println!("Formatted {}", path.display());
+        }
+    }
+    Ok(!check || unformatted.is_empty())
+}
+
+/// Formats the source of a locale file.
+fn format_file(src: &str) -> Result<String, serde_json::Error> {
+    let keys: Map<String, Value> = serde_json::from_str(src)?;
+    let mut out = String::new();
+    write_keys(&mut out, &keys, 0);
+    out.push('\n');
+    Ok(out)
+}
+
+/// Writes a map of keys, with each `@key` entry after the key it describes.
+fn write_keys(out: &mut String, keys: &Map<String, Value>, depth: usize) {
+    let mut entries: Vec<_> = keys.iter().collect();
+    entries.sort_by_key(|(k, _)| match k.strip_prefix('@') {
+        Some(k) => (k, true),
+        None => (k.as_str(), false),
+    });
+    write_object(out, entries, depth, |out, key, value, depth| match value {
+        Value::Object(keys) if !key.starts_with('@') => write_keys(out, keys, depth),
+        value => write_json(out, value, depth),
+    });
+}
+
+/// Writes a JSON value, such as the metadata of a key.
+fn write_json(out: &mut String, value: &Value, depth: usize) {
+    match value {
+        Value::Object(map) => {
+            write_object(out, map.iter().collect(), depth, |out, _, value, depth| {
+                write_json(out, value, depth)
+            })
+        }
+        Value::Array(items) if !items.is_empty() => {
+            out.push_str("[\n");
+            for (i, item) in items.iter().enumerate() {
+                out.push_str(&INDENT.repeat(depth + 1));
+                write_json(out, item, depth + 1);
+                out.push_str(if i + 1 < items.len() { ",\n" } else { "\n" });
+            }
+            out.push_str(&INDENT.repeat(depth));
+            out.push(']');
+        }
+        value => {
+            let _ = write!(out, "{}", value);
+        }
+    }
+}
+
+/// Writes the entries of an object, with `write_value` writing each value.
+fn write_object(
+    out: &mut String,
+    entries: Vec<(&String, &Value)>,
+    depth: usize,
+    write_value: impl Fn(&mut String, &str, &Value, usize),
+) {
+    if entries.is_empty() {
+        out.push_str("{}");
+        return;
+    }
+    out.push_str("{\n");
+    for (i, (key, value)) in entries.iter().enumerate() {
+        let _ = write!(
+            out,
+            "{}{}: ",
+            INDENT.repeat(depth + 1),
+            Value::from(key.as_str())
+        );
+        write_value(out, key, value, depth + 1);
+        out.push_str(if i + 1 < entries.len() { ",\n" } else { "\n" });
+    }
+    out.push_str(&INDENT.repeat(depth));
+    out.push('}');
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn format_locale_file() {
+        let src = r#"{"b": "{ n, plural, one {# item} other {# items} }", "@a": {"maxLength": 10,
+            "description": "A"}, "a": "It's {name}", "c": {"e": "E", "d": "{broken"},
+            "@c": {"screenshots": ["c.png"]}, "empty": {}}"#;
+        let formatted = format_file(src).unwrap();
+        expect_test::expect![[r#"
+            {
+                "a": "It's {name}",
+                "@a": {
+                    "description": "A",
+                    "maxLength": 10
+                },
+                "b": "{ n, plural, one {# item} other {# items} }",
+                "c": {
+                    "d": "{broken",
+                    "e": "E"
+                },
+                "@c": {
+                    "screenshots": [
+                        "c.png"
+                    ]
+                },
+                "empty": {}
+            }
+        "#]]
+        .assert_eq(&formatted);
+        assert_eq!(format_file(&formatted).unwrap(), formatted);
+    }
+}
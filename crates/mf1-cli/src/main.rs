@@ -0,0 +1,226 @@
+//! The `mf1` command, for maintaining the catalogs of `mf1` crates.
+//!
+//! Every command reads the catalog configured in `[package.metadata.mf1]` of
+//! a package, except `convert` when it is given a file.
+
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+use clap::{value_parser, Arg, ArgAction, ArgGroup, ArgMatches, Command};
+use mf1_build::{CatalogConfig, LocaleEntry};
+use thiserror::Error;
+
+mod convert;
+mod fmt;
+mod report;
+
+#[derive(Debug, Error)]
+enum Error {
+    #[error(transparent)]
+    Catalog(#[from] mf1_build::Error),
+    #[error(transparent)]
+    Convert(#[from] mf1_convert::Error),
+    #[error("Error reading {path:?}: {err}")]
+    Read { path: PathBuf, err: std::io::Error },
+    #[error("Error writing {path:?}: {err}")]
+    Write { path: PathBuf, err: std::io::Error },
+    #[error("Parsing of file {path:?} failed: {err}")]
+    Json {
+        path: PathBuf,
+        err: serde_json::Error,
+    },
+    #[error("No Cargo.toml found in {0:?} or its parents")]
+    NoManifest(PathBuf),
+    #[error("Locale {0:?} is not in the catalog")]
+    UnknownLocale(String),
+    #[error("Can't tell the format of {0:?} from its extension, use --from or --to")]
+    UnknownFormat(PathBuf),
+}
+
+fn cli() -> Command {
+    let manifest_path = Arg::new("manifest-path")
+        .long("manifest-path")
+        .value_name("PATH")
+        .value_parser(value_parser!(PathBuf))
+        .global(true)
+        .help("Path to the Cargo.toml of the package with the catalog");
+    let format = |name: &'static str| {
+        Arg::new(name)
+            .long(name)
+            .value_name("FORMAT")
+            .value_parser(["json", "android", "apple"])
+    };
+    Command::new("mf1")
+        .version(env!("CARGO_PKG_VERSION"))
+        .about("Maintain the message catalogs of mf1 crates")
+        .subcommand_required(true)
+        .arg(manifest_path)
+        .subcommand(Command::new("check").about("Parse every message, and report errors"))
+        .subcommand(
+            Command::new("stats").about("Show how much of the base locale each locale translates"),
+        )
+        .subcommand(
+            Command::new("missing")
+                .about("List the keys of the base locale each locale doesn't translate")
+                .arg(
+                    Arg::new("locales")
+                        .value_name("LOCALE")
+                        .num_args(0..)
+                        .help("The locales to list, instead of every locale"),
+                ),
+        )
+        .subcommand(
+            Command::new("keys")
+                .about("List the keys no message macro uses, and the keys used that don't exist")
+                .arg(
+                    Arg::new("src")
+                        .long("src")
+                        .value_name("DIR")
+                        .value_parser(value_parser!(PathBuf))
+                        .action(ArgAction::Append)
+                        .help("A directory of Rust sources to scan, instead of src"),
+                ),
+        )
+        .subcommand(
+            Command::new("fmt")
+                .about("Sort and indent the keys of the locale files")
+                .arg(
+                    Arg::new("check")
+                        .long("check")
+                        .action(ArgAction::SetTrue)
+                        .help("List the files that aren't formatted, without changing them"),
+                ),
+        )
+        .subcommand(
+            Command::new("convert")
+                .about("Convert a catalog between JSON, Android and Apple formats")
+                .arg(
+                    Arg::new("input")
+                        .value_name("INPUT")
+                        .value_parser(value_parser!(PathBuf))
+                        .help("The file to convert"),
+                )
+                .arg(
+                    Arg::new("locale")
+                        .long("locale")
+                        .value_name("LOCALE")
+                        .help("Convert a locale of the catalog, instead of a file"),
+                )
+                .group(
+                    ArgGroup::new("source")
+                        .args(["input", "locale"])
+                        .required(true),
+                )
+                .arg(
+                    Arg::new("output")
+                        .short('o')
+                        .long("output")
+                        .value_name("OUTPUT")
+                        .value_parser(value_parser!(PathBuf))
+                        .required(true)
+                        .help("The file to write"),
+                )
+                .arg(format("from").help("The format of the input"))
+                .arg(format("to").help("The format of the output")),
+        )
+}
+
+/// Finds the directory of the package: the one given by `--manifest-path`,
+/// or the nearest one with a `Cargo.toml` above the current directory.
+fn manifest_dir(matches: &ArgMatches) -> Result<PathBuf, Error> {
+    if let Some(path) = matches.get_one::<PathBuf>("manifest-path") {
+        let dir = path.parent().unwrap_or(Path::new(""));
+        return Ok(if dir.as_os_str().is_empty() {
+            PathBuf::from(".")
+        } else {
+            dir.to_owned()
+        });
+    }
+    let current_dir = std::env::current_dir().map_err(|err| Error::Read {
+        path: PathBuf::from("."),
+        err,
+    })?;
+    current_dir
+        .ancestors()
+        .find(|dir| dir.join("Cargo.toml").is_file())
+        .map(Path::to_owned)
+        .ok_or(Error::NoManifest(current_dir))
+}
+
+/// Finds a locale of the catalog by its tag, or by the name it is listed as.
+fn find_locale<'c>(catalog: &'c CatalogConfig, locale: &str) -> Result<&'c LocaleEntry, Error> {
+    catalog
+        .locales
+        .iter()
+        .find(|entry| entry.tag.eq_ignore_ascii_case(locale) || entry.file == locale)
+        .ok_or_else(|| Error::UnknownLocale(locale.to_owned()))
+}
+
+/// Runs a command, returning whether it found no problems.
+fn run(matches: &ArgMatches) -> Result<bool, Error> {
+    let catalog = || -> Result<(PathBuf, CatalogConfig), Error> {
+        let dir = manifest_dir(matches)?;
+        let catalog = CatalogConfig::read(&dir)?;
+        Ok((dir, catalog))
+    };
+    match matches.subcommand() {
+        Some(("check", _)) => {
+            let (dir, catalog) = catalog()?;
+            report::check(&dir, &catalog)
+        }
+        Some(("stats", _)) => report::stats(&catalog()?.1),
+        Some(("missing", args)) => {
+            let locales: Vec<&String> = args.get_many("locales").unwrap_or_default().collect();
+            report::missing(&catalog()?.1, &locales)
+        }
+        Some(("keys", args)) => {
+            let (dir, catalog) = catalog()?;
+            let src_dirs: Vec<PathBuf> = match args.get_many::<PathBuf>("src") {
+                Some(dirs) => dirs.cloned().collect(),
+                None => vec![dir.join("src")],
+            };
+            report::keys(&catalog, &src_dirs)
+        }
+        Some(("fmt", args)) => fmt::fmt(&catalog()?.1, args.get_flag("check")),
+        Some(("convert", args)) => {
+            let output = args
+                .get_one::<PathBuf>("output")
+                .expect("output is required");
+            let to = convert::Format::for_file(args.get_one("to"), output)?;
+            let source = match args.get_one::<PathBuf>("input") {
+                Some(input) => convert::read(input, args.get_one("from"))?,
+                None => {
+                    let locale = args
+                        .get_one::<String>("locale")
+                        .expect("source is required");
+                    convert::read_locale(&catalog()?.1, locale)?
+                }
+            };
+            convert::write(&source, output, to)?;
+            Ok(true)
+        }
+        _ => unreachable!("a subcommand is required"),
+    }
+}
+
+fn main() -> ExitCode {
+    let matches = cli().get_matches();
+    match run(&matches) {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::FAILURE,
+        Err(err) => {
+            eprintln!("error: {}", err);
+            ExitCode::from(2)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn valid_cli() {
+        cli().debug_assert();
+    }
+}
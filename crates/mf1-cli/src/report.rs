@@ -0,0 +1,202 @@
+//! The `check`, `stats`, `missing` and `keys` commands.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use mf1_build::key_usage::{self, KeyUsage};
+use mf1_build::{Builder, CatalogConfig, StringSet};
+use mf1_parser::{parse, LexerSpan};
+
+use crate::{find_locale, Error};
+
+/// Loads every locale of the catalog, with the base locale first.
+fn load_locales(catalog: &CatalogConfig) -> Result<Vec<StringSet<'_>>, Error> {
+    let mut locales = catalog
+        .locales
+        .iter()
+        .map(|entry| catalog.load(entry))
+        .collect::<Result<Vec<_>, _>>()?;
+    locales.sort_by_key(|locale| locale.name != catalog.base_locale);
+    Ok(locales)
+}
+
+/// Parses every message, printing the errors with the part of the message
+/// they are in. When every message parses, the catalog is generated to find
+/// the errors `load_locales!` would report, such as arguments used as
+/// different types in different locales.
+pub fn check(manifest_dir: &Path, catalog: &CatalogConfig) -> Result<bool, Error> {
+    let mut errors = 0;
+    for locale in load_locales(catalog)? {
+        for (key, src) in locale.messages() {
+            if let Err((message, span)) = parse::<&str>(src) {
+                errors += 1;
+                println!("error: key {} in {}: {}", key, locale.name, message);
+                println!("{}", snippet(src, span));
+            }
+        }
+    }
+    if errors == 0 {
+        if let Err(err) = Builder::new().manifest_dir(manifest_dir).generate() {
+            errors += 1;
+            println!("error: {}", err);
+        }
+    }
+    match errors {
+        0 => println!("{} locales checked", catalog.locales.len()),
+        1 => println!("1 error"),
+        n => println!("{} errors", n),
+    }
+    Ok(errors == 0)
+}
+
+/// Renders the line of a message a span starts on, with carets under the span.
+fn snippet(src: &str, span: LexerSpan) -> String {
+    let start = span.start.min(src.len());
+    let line_start = src[..start].rfind('\n').map_or(0, |i| i + 1);
+    let line_end = src[start..].find('\n').map_or(src.len(), |i| start + i);
+    let end = span.end.clamp(start, line_end);
+    let indent = src[line_start..start].chars().count();
+    let width = src[start..end].chars().count().max(1);
+    format!(
+        "    | {}\n    | {}{}",
+        &src[line_start..line_end],
+        " ".repeat(indent),
+        "^".repeat(width)
+    )
+}
+
+/// The keys of the base locale which are meant to be translated.
+fn translatable_keys(base: &StringSet) -> Vec<String> {
+    let metadata = base.all_metadata();
+    base.messages()
+        .into_keys()
+        .filter(|key| metadata.get(key).and_then(|m| m.translate) != Some(false))
+        .collect()
+}
+
+/// The keys of the base locale each locale doesn't translate, by tag. Keys
+/// marked as not translatable are left out.
+fn missing_keys<'a>(locales: &'a [StringSet]) -> BTreeMap<&'a str, Vec<String>> {
+    let (base, locales) = locales.split_first().expect("the base locale is loaded");
+    let base_keys = translatable_keys(base);
+    locales
+        .iter()
+        .map(|locale| {
+            let messages = locale.messages();
+            let missing = base_keys
+                .iter()
+                .filter(|key| !messages.contains_key(*key))
+                .cloned()
+                .collect();
+            (locale.name, missing)
+        })
+        .collect()
+}
+
+/// Prints how many of the translatable keys of the base locale each locale
+/// translates.
+pub fn stats(catalog: &CatalogConfig) -> Result<bool, Error> {
+    let locales = load_locales(catalog)?;
+    let total = translatable_keys(&locales[0]).len();
+    let missing = missing_keys(&locales);
+    let width = locales
+        .iter()
+        .map(|locale| locale.name.len())
+        .chain(["Locale".len()])
+        .max()
+        .unwrap_or_default();
+    println!("{:<width$}  Translated  Complete", "Locale");
+    for locale in &locales {
+        let translated = total - missing.get(locale.name).map_or(0, Vec::len);
+        let complete = if total == 0 {
+            100.0
+        } else {
+            translated as f64 * 100.0 / total as f64
+        };
+        let fraction = format!("{}/{}", translated, total);
+        println!(
+            "{:<width$}  {:>10}  {:>7.1}%",
+            locale.name, fraction, complete
+        );
+    }
+    Ok(true)
+}
+
+/// Prints the keys each locale doesn't translate, returning whether every
+/// locale translates every key.
+pub fn missing(catalog: &CatalogConfig, filter: &[&String]) -> Result<bool, Error> {
+    let tags = filter
+        .iter()
+        .map(|locale| find_locale(catalog, locale).map(|entry| entry.tag.as_str()))
+        .collect::<Result<Vec<_>, _>>()?;
+    let locales = load_locales(catalog)?;
+    let mut complete = true;
+    for (tag, keys) in missing_keys(&locales) {
+        if !tags.is_empty() && !tags.contains(&tag) {
+            continue;
+        }
+        if keys.is_empty() {
+            continue;
+        }
+        complete = false;
+        println!("{}: {} missing", tag, keys.len());
+        for key in keys {
+            println!("    {}", key);
+        }
+    }
+    Ok(complete)
+}
+
+/// Prints the keys of the base locale which no message macro in `src_dirs`
+/// uses, and the keys used which aren't in the base locale. Returns whether
+/// there are neither.
+pub fn keys(catalog: &CatalogConfig, src_dirs: &[PathBuf]) -> Result<bool, Error> {
+    let base = catalog
+        .locales
+        .iter()
+        .find(|entry| entry.tag == catalog.base_locale)
+        .expect("the base locale is listed");
+    let base = catalog.load(base)?;
+    let mut usages: Vec<KeyUsage> = Vec::new();
+    for dir in src_dirs {
+        usages.extend(key_usage::scan_dir(dir)?);
+    }
+    let report = key_usage::compare(&base, usages);
+    if !report.unused.is_empty() {
+        println!("{} unused:", report.unused.len());
+        for key in &report.unused {
+            println!("    {}", key);
+        }
+    }
+    if !report.undefined.is_empty() {
+        println!("{} undefined:", report.undefined.len());
+        for usage in &report.undefined {
+            println!(
+                "    {} at {}:{}:{}",
+                usage.path(),
+                usage.file.display(),
+                usage.line,
+                usage.column
+            );
+        }
+    }
+    Ok(report.unused.is_empty() && report.undefined.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn error_snippet() {
+        assert_eq!(
+            snippet("Hello {name", 6..11),
+            "    | Hello {name\n    |       ^^^^^"
+        );
+        assert_eq!(
+            snippet("Première\n{n, plural, öne {}}", 22..26),
+            "    | {n, plural, öne {}}\n    |             ^^^"
+        );
+        assert_eq!(snippet("Hello {", 7..7), "    | Hello {\n    |        ^");
+    }
+}
@@ -0,0 +1,510 @@
+//! Android `strings.xml` resources.
+//!
+//! Plain messages become `<string>` elements and messages with a `plural`
+//! argument become `<plurals>` elements. Placeholders are written as
+//! `%1$s` (or `%1$d` for the plural count) wrapped in `<xliff:g>` tags, whose
+//! `id` attribute records the argument name so it survives a round trip.
+//! Placeholders without an `<xliff:g>` tag are imported as `arg1`, `arg2`, etc.
+
+use mf1_parser::{to_source, PluralCase, PluralCategory, Token};
+use quick_xml::escape::{escape, resolve_xml_entity};
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+use quick_xml::XmlVersion;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+use crate::format::{
+    arg_names, check_arg_name, check_supported, parse_format, parse_message, positional_name,
+    push_content, resolve_index, to_pieces, Piece, Segment, Style, Tokens,
+};
+use crate::{Catalog, Error};
+
+const XLIFF_NS: &str = "urn:oasis:names:tc:xliff:document:1.2";
+
+/// Writes a catalog as an Android `strings.xml` file.
+pub fn to_strings_xml(catalog: &Catalog) -> Result<String, Error> {
+    let mut out = String::new();
+    out.push_str("<?xml version=\"1.0\" encoding=\"utf-8\"?>\n");
+    let _ = writeln!(out, "<resources xmlns:xliff=\"{}\">", XLIFF_NS);
+    for (key, src) in catalog.iter() {
+        let tokens = parse_message(key, src)?;
+        let args = arg_names(&tokens);
+        if let Some(description) = catalog.description(key) {
+            let _ = writeln!(out, "    <!-- {} -->", description.replace("--", "- -"));
+        }
+        match check_supported(key, &tokens)? {
+            0 => {
+                let mut pieces = Vec::new();
+                to_pieces(&tokens, &args, &[], Style::Android, None, &mut pieces);
+                let _ = writeln!(
+                    out,
+                    "    <string name=\"{}\">{}</string>",
+                    escape(key),
+                    write_pieces(&pieces)
+                );
+            }
+            1 => {
+                let position = tokens
+                    .iter()
+                    .position(|t| matches!(t, Token::Plural { .. }))
+                    .expect("message has a plural");
+                let Token::Plural { arg, cases, .. } = &tokens[position] else {
+                    unreachable!()
+                };
+                let _ = writeln!(out, "    <plurals name=\"{}\">", escape(key));
+                for case in cases.iter() {
+                    let mut pieces = Vec::new();
+                    for part in [&tokens[..position], &case.tokens, &tokens[position + 1..]] {
+                        to_pieces(part, &args, &[arg], Style::Android, Some(arg), &mut pieces);
+                    }
+                    let _ = writeln!(
+                        out,
+                        "        <item quantity=\"{}\">{}</item>",
+                        mf1_parser::plural_category_name(case.key),
+                        write_pieces(&pieces)
+                    );
+                }
+                out.push_str("    </plurals>\n");
+            }
+            _ => {
+                return Err(Error::MultiplePlurals {
+                    key: key.to_owned(),
+                })
+            }
+        }
+    }
+    out.push_str("</resources>\n");
+    Ok(out)
+}
+
+fn write_pieces(pieces: &[Piece]) -> String {
+    let mut out = String::new();
+    for (i, piece) in pieces.iter().enumerate() {
+        match piece {
+            Piece::Text(text) => {
+                escape_text(&mut out, text, i == 0, i == pieces.len() - 1);
+            }
+            Piece::Placeholder { name, spec } => {
+                let _ = write!(out, "<xliff:g id=\"{}\">{}</xliff:g>", escape(name), spec);
+            }
+        }
+    }
+    out
+}
+
+/// Escapes text for Android, which collapses whitespace and gives meaning to
+/// quotes, backslashes and leading `@` or `?`.
+fn escape_text(out: &mut String, text: &str, first: bool, last: bool) {
+    let mut previous_space = first;
+    let mut chars = text.chars().peekable();
+    let mut start = true;
+    while let Some(c) = chars.next() {
+        let is_last = last && chars.peek().is_none();
+        match c {
+            '\\' => out.push_str("\\\\"),
+            '\'' => out.push_str("\\'"),
+            '"' => out.push_str("\\\""),
+            '\n' => out.push_str("\\n"),
+            '\t' => out.push_str("\\t"),
+            '@' | '?' if first && start => {
+                out.push('\\');
+                out.push(c);
+            }
+            ' ' if previous_space || is_last => out.push_str("\\u0020"),
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            c => out.push(c),
+        }
+        previous_space = c == ' ';
+        start = false;
+    }
+}
+
+/// Reads an Android `strings.xml` file into a catalog.
+///
+/// `<string>` and `<plurals>` elements are imported; other resources are skipped.
+/// A comment directly before an element becomes the description of its key.
+pub fn from_strings_xml(src: &str) -> Result<Catalog, Error> {
+    let mut reader = Reader::from_str(src);
+    let mut catalog = Catalog::default();
+    let mut comment = None;
+    loop {
+        let event = reader.read_event()?;
+        let key = match &event {
+            Event::Start(e) | Event::Empty(e)
+                if matches!(e.local_name().as_ref(), "string" | "plurals") =>
+            {
+                let key = attribute(e, "name")?;
+                if let Some(description) = comment.take() {
+                    catalog.set_description(key.clone(), description);
+                }
+                key
+            }
+            Event::Comment(e) => {
+                comment = Some(e.xml10_content().trim().to_owned());
+                continue;
+            }
+            Event::Text(e) if e.trim().is_empty() => continue,
+            _ => {
+                comment = None;
+                String::new()
+            }
+        };
+        match event {
+            Event::Start(e) if e.local_name().as_ref() == "string" => {
+                let content = read_content(&mut reader, "string")?;
+                catalog.insert(key.clone(), string_message(&key, &content)?);
+            }
+            Event::Empty(e) if e.local_name().as_ref() == "string" => {
+                catalog.insert(key, "");
+            }
+            Event::Start(e) if e.local_name().as_ref() == "plurals" => {
+                let mut items = Vec::new();
+                loop {
+                    match reader.read_event()? {
+                        Event::Start(e) if e.local_name().as_ref() == "item" => {
+                            let quantity = attribute(&e, "quantity")?;
+                            items.push((quantity, read_content(&mut reader, "item")?));
+                        }
+                        Event::Empty(e) if e.local_name().as_ref() == "item" => {
+                            items.push((attribute(&e, "quantity")?, Vec::new()));
+                        }
+                        Event::End(e) if e.local_name().as_ref() == "plurals" => break,
+                        Event::Eof => return Err(unexpected_eof()),
+                        _ => {}
+                    }
+                }
+                catalog.insert(key.clone(), plurals_message(&key, &items)?);
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+    Ok(catalog)
+}
+
+fn attribute(e: &BytesStart, name: &str) -> Result<String, Error> {
+    match e.try_get_attribute(name).map_err(quick_xml::Error::from)? {
+        Some(attr) => Ok(attr.normalized_value(XmlVersion::Implicit1_0)?.into_owned()),
+        None => Err(Error::InvalidEntry {
+            key: e.name().as_ref().to_owned(),
+            message: format!("missing {:?} attribute", name),
+        }),
+    }
+}
+
+fn unexpected_eof() -> Error {
+    Error::Xml(quick_xml::Error::Syntax(
+        quick_xml::errors::SyntaxError::UnclosedTag,
+    ))
+}
+
+/// The raw text of an element, split where `<xliff:g>` tags name a placeholder.
+type RawContent = Vec<(String, Option<String>)>;
+
+fn read_content(reader: &mut Reader<&[u8]>, end: &str) -> Result<RawContent, Error> {
+    let mut content: RawContent = vec![(String::new(), None)];
+    let mut depth = 0;
+    loop {
+        match reader.read_event()? {
+            Event::Text(e) => content.last_mut().unwrap().0.push_str(&e.xml10_content()),
+            Event::CData(e) => content.last_mut().unwrap().0.push_str(&e),
+            Event::GeneralRef(e) => {
+                let text = &mut content.last_mut().unwrap().0;
+                if let Some(c) = e.resolve_char_ref()? {
+                    text.push(c);
+                } else {
+                    let name = e.xml10_content();
+                    text.push_str(resolve_xml_entity(&name).unwrap_or(&name));
+                }
+            }
+            Event::Start(e) if e.local_name().as_ref() == "g" => {
+                content.push((String::new(), Some(attribute(&e, "id")?)));
+            }
+            Event::End(e) if e.local_name().as_ref() == "g" => {
+                content.push((String::new(), None));
+            }
+            Event::Start(e) => {
+                depth += 1;
+                let _ = write!(content.last_mut().unwrap().0, "<{}>", &*e);
+            }
+            Event::Empty(e) => {
+                let _ = write!(content.last_mut().unwrap().0, "<{}/>", &*e);
+            }
+            Event::End(e) if depth == 0 && e.local_name().as_ref() == end => break,
+            Event::End(e) => {
+                depth -= 1;
+                let _ = write!(content.last_mut().unwrap().0, "</{}>", e.name().as_ref());
+            }
+            Event::Eof => return Err(unexpected_eof()),
+            _ => {}
+        }
+    }
+    Ok(unescape(content))
+}
+
+/// Applies Android's string processing: escapes, quoting and whitespace collapsing.
+fn unescape(content: RawContent) -> RawContent {
+    // Each character is tagged with whether it is whitespace that may be trimmed.
+    let mut chars: Vec<(char, bool)> = Vec::new();
+    let mut bounds = Vec::new();
+    let mut in_quotes = false;
+    for (raw, id) in content {
+        let start = chars.len();
+        let mut iter = raw.chars();
+        while let Some(c) = iter.next() {
+            match c {
+                '"' => in_quotes = !in_quotes,
+                '\\' => match iter.next() {
+                    Some('n') => chars.push(('\n', false)),
+                    Some('t') => chars.push(('\t', false)),
+                    Some('u') => {
+                        let hex: String = iter.by_ref().take(4).collect();
+                        match u32::from_str_radix(&hex, 16).ok().and_then(char::from_u32) {
+                            Some(c) => chars.push((c, false)),
+                            None => chars.extend(hex.chars().map(|c| (c, false))),
+                        }
+                    }
+                    Some(c) => chars.push((c, false)),
+                    None => {}
+                },
+                c if c.is_whitespace() && !in_quotes => {
+                    if !matches!(chars.last(), Some((_, true))) {
+                        chars.push((' ', true));
+                    }
+                }
+                c => chars.push((c, false)),
+            }
+        }
+        bounds.push((start, chars.len(), id));
+    }
+    let leading = chars.iter().take_while(|(_, soft)| *soft).count();
+    let trailing = chars.len()
+        - leading
+        - chars[leading..]
+            .iter()
+            .rev()
+            .skip_while(|(_, soft)| *soft)
+            .count();
+    let (min, max) = (leading, chars.len() - trailing);
+    bounds
+        .into_iter()
+        .map(|(start, end, id)| {
+            let (start, end) = (start.clamp(min, max), end.clamp(min, max));
+            (chars[start..end].iter().map(|(c, _)| c).collect(), id)
+        })
+        .collect()
+}
+
+/// Finds the argument name of every placeholder that sits in an `<xliff:g>` tag.
+fn placeholder_names(content: &RawContent, names: &mut BTreeMap<usize, String>) {
+    let mut next = 0;
+    for (text, id) in content {
+        for segment in parse_format(text) {
+            if let Segment::Placeholder { index, .. } = segment {
+                let index = resolve_index(index, &mut next);
+                if let Some(id) = id {
+                    names.entry(index).or_insert_with(|| id.clone());
+                }
+            }
+        }
+    }
+}
+
+fn segments(content: &RawContent) -> Vec<(Segment, usize)> {
+    let mut next = 0;
+    content
+        .iter()
+        .flat_map(|(text, _)| parse_format(text))
+        .map(|segment| {
+            let index = match &segment {
+                Segment::Placeholder { index, .. } | Segment::Variable { index, .. } => {
+                    resolve_index(*index, &mut next)
+                }
+                Segment::Text(_) => 0,
+            };
+            (segment, index)
+        })
+        .collect()
+}
+
+fn name_for(key: &str, names: &BTreeMap<usize, String>, index: usize) -> Result<String, Error> {
+    let name = names
+        .get(&index)
+        .cloned()
+        .unwrap_or_else(|| positional_name(index));
+    check_arg_name(key, &name)?;
+    Ok(name)
+}
+
+fn string_message(key: &str, content: &RawContent) -> Result<String, Error> {
+    let mut names = BTreeMap::new();
+    placeholder_names(content, &mut names);
+    let mut tokens = Tokens::new();
+    for (segment, index) in segments(content) {
+        match segment {
+            Segment::Text(text) => push_content(&mut tokens, &text),
+            Segment::Placeholder { .. } | Segment::Variable { .. } => {
+                tokens.push(Token::PlainArg {
+                    arg: name_for(key, &names, index)?,
+                })
+            }
+        }
+    }
+    Ok(to_source(&tokens))
+}
+
+fn plurals_message(key: &str, items: &[(String, RawContent)]) -> Result<String, Error> {
+    let mut names = BTreeMap::new();
+    for (_, content) in items {
+        placeholder_names(content, &mut names);
+    }
+    // The quantity is conventionally the first integer placeholder.
+    let count_index = items
+        .iter()
+        .flat_map(|(_, content)| segments(content))
+        .filter(|(segment, _)| segment.is_integer())
+        .map(|(_, index)| index)
+        .min();
+    let count_name = match count_index {
+        Some(index) => name_for(key, &names, index)?,
+        None => "count".to_owned(),
+    };
+    let mut cases = Vec::new();
+    for (quantity, content) in items {
+        let category =
+            PluralCategory::get_for_cldr_string(quantity).ok_or_else(|| Error::InvalidEntry {
+                key: key.to_owned(),
+                message: format!("{:?} is not a plural quantity", quantity),
+            })?;
+        let mut tokens = Tokens::new();
+        for (segment, index) in segments(content) {
+            match segment {
+                Segment::Text(text) => push_content(&mut tokens, &text),
+                _ if Some(index) == count_index => tokens.push(Token::Octothorpe {}),
+                _ => tokens.push(Token::PlainArg {
+                    arg: name_for(key, &names, index)?,
+                }),
+            }
+        }
+        cases.push(PluralCase {
+            key: category,
+            tokens: tokens.into(),
+        });
+    }
+    Ok(to_source(&[Token::Plural {
+        arg: count_name,
+        cases: cases.into(),
+        plural_offset: None,
+    }]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn catalog(messages: &[(&str, &str)]) -> Catalog {
+        let mut catalog = Catalog::default();
+        for (key, message) in messages {
+            catalog.insert(*key, *message);
+        }
+        catalog
+    }
+
+    #[test]
+    fn export() {
+        let mut catalog = catalog(&[
+            ("greeting", "Hello {name}, it's 100% done!"),
+            ("nested.plain", "  Don't \"quote\" me @ all"),
+            (
+                "items",
+                "You have {count, plural, one {# item} other {# items}} in {place}",
+            ),
+        ]);
+        catalog.set_description("greeting", "Shown on launch -- once");
+        assert_eq!(
+            to_strings_xml(&catalog).unwrap(),
+            r#"<?xml version="1.0" encoding="utf-8"?>
+<resources xmlns:xliff="urn:oasis:names:tc:xliff:document:1.2">
+    <!-- Shown on launch - - once -->
+    <string name="greeting">Hello <xliff:g id="name">%1$s</xliff:g>, it\'s 100%% done!</string>
+    <plurals name="items">
+        <item quantity="one">You have <xliff:g id="count">%1$d</xliff:g> item in <xliff:g id="place">%2$s</xliff:g></item>
+        <item quantity="other">You have <xliff:g id="count">%1$d</xliff:g> items in <xliff:g id="place">%2$s</xliff:g></item>
+    </plurals>
+    <string name="nested.plain">\u0020\u0020Don\'t \"quote\" me @ all</string>
+</resources>
+"#
+        );
+    }
+
+    #[test]
+    fn export_unsupported() {
+        let select = catalog(&[("select", "{g, select, male {He} other {They}}")]);
+        assert!(matches!(
+            to_strings_xml(&select),
+            Err(Error::Unsupported { kind: "select", .. })
+        ));
+        let plurals = catalog(&[("two", "{a, plural, other {#}} {b, plural, other {#}}")]);
+        assert!(matches!(
+            to_strings_xml(&plurals),
+            Err(Error::MultiplePlurals { .. })
+        ));
+    }
+
+    #[test]
+    fn import() {
+        let src = r#"<?xml version="1.0" encoding="utf-8"?>
+<resources xmlns:xliff="urn:oasis:names:tc:xliff:document:1.2">
+    <!-- The name of the app -->
+    <string name="app_name" translatable="false">My   App</string>
+    <string name="welcome">Welcome, <xliff:g id="user" example="Bob">%1$s</xliff:g>! You\'re #%2$d &amp; <b>{great}</b></string>
+    <string name="quoted">"  keep  spaces  "\n</string>
+    <string-array name="planets"><item>Mercury</item></string-array>
+    <plurals name="songs">
+        <item quantity="one">%d song found in %s</item>
+        <item quantity="other">%d songs found in %s</item>
+    </plurals>
+</resources>
+"#;
+        let catalog = from_strings_xml(src).unwrap();
+        assert_eq!(catalog.description("app_name"), Some("The name of the app"));
+        assert_eq!(catalog.description("welcome"), None);
+        assert_eq!(
+            catalog.messages,
+            [
+                ("app_name", "My App"),
+                ("quoted", "  keep  spaces  \n"),
+                (
+                    "songs",
+                    "{arg1, plural, one {# song found in {arg2}} other {# songs found in {arg2}}}"
+                ),
+                (
+                    "welcome",
+                    "Welcome, {user}! You''re '#'{arg2} & <b>'{'great'}'</b>"
+                ),
+            ]
+            .into_iter()
+            .map(|(k, v)| (k.to_owned(), v.to_owned()))
+            .collect()
+        );
+    }
+
+    #[test]
+    fn roundtrip() {
+        let mut catalog = catalog(&[
+            ("greeting", "Hello {name}, it''s 100% done!"),
+            ("discount", "Save 50% discount"),
+            ("spaces", " a  b "),
+            (
+                "items",
+                "{count, plural, one {# item in {place}} other {# items in {place}}}",
+            ),
+        ]);
+        catalog.set_description("spaces", "Whitespace is kept");
+        let xml = to_strings_xml(&catalog).unwrap();
+        assert_eq!(from_strings_xml(&xml).unwrap(), catalog);
+    }
+}
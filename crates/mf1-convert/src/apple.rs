@@ -0,0 +1,531 @@
+//! Apple `.strings` and `.stringsdict` resources.
+//!
+//! Messages without plurals are written to `.strings` files, with placeholders
+//! as `%1$@`. Messages with `plural` arguments are written to `.stringsdict`
+//! files instead, where each plural becomes a `NSStringPluralRuleType`
+//! variable named after the argument, so argument names survive a round
+//! trip. Other placeholders are imported as `arg1`, `arg2`, etc.
+
+use mf1_parser::{plural_category_name, to_source, PluralCase, PluralCategory, Token};
+use plist::{Dictionary, Value};
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+use crate::format::{
+    arg_names, check_arg_name, check_supported, parse_format, parse_message, positional_name,
+    push_content, resolve_index, to_pieces, Piece, Segment, Style, Tokens,
+};
+use crate::{Catalog, Error};
+
+const FORMAT_KEY: &str = "NSStringLocalizedFormatKey";
+const SPEC_TYPE_KEY: &str = "NSStringFormatSpecTypeKey";
+const VALUE_TYPE_KEY: &str = "NSStringFormatValueTypeKey";
+const PLURAL_RULE_TYPE: &str = "NSStringPluralRuleType";
+
+/// Writes the messages of a catalog that have no plurals as a `.strings` file.
+///
+/// Messages with plurals are written by [`to_stringsdict`]. Descriptions are
+/// written as comments before their entries.
+pub fn to_strings(catalog: &Catalog) -> Result<String, Error> {
+    let mut out = String::new();
+    for (key, src) in catalog.iter() {
+        let tokens = parse_message(key, src)?;
+        if check_supported(key, &tokens)? > 0 {
+            continue;
+        }
+        let args = arg_names(&tokens);
+        let mut pieces = Vec::new();
+        to_pieces(&tokens, &args, &[], Style::Apple, None, &mut pieces);
+        if let Some(description) = catalog.description(key) {
+            let _ = writeln!(out, "/* {} */", description.replace("*/", "* /"));
+        }
+        let _ = writeln!(out, "\"{}\" = \"{}\";", escape(key), escape(&join(&pieces)));
+    }
+    Ok(out)
+}
+
+fn join(pieces: &[Piece]) -> String {
+    pieces
+        .iter()
+        .map(|piece| match piece {
+            Piece::Text(text) => text.as_str(),
+            Piece::Placeholder { spec, .. } => spec.as_str(),
+        })
+        .collect()
+}
+
+fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\t' => out.push_str("\\t"),
+            '\r' => out.push_str("\\r"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// Writes the messages of a catalog that have plurals as a `.stringsdict` file.
+///
+/// Messages without plurals are written by [`to_strings`].
+pub fn to_stringsdict(catalog: &Catalog) -> Result<String, Error> {
+    let mut root = Dictionary::new();
+    for (key, src) in catalog.iter() {
+        let tokens = parse_message(key, src)?;
+        if check_supported(key, &tokens)? == 0 {
+            continue;
+        }
+        let args = arg_names(&tokens);
+        let numeric: Vec<&str> = tokens
+            .iter()
+            .filter_map(|token| match token {
+                Token::Plural { arg, .. } => Some(arg.as_str()),
+                _ => None,
+            })
+            .collect();
+        let mut entry = Dictionary::new();
+        let mut variables = Dictionary::new();
+        let mut pieces = Vec::new();
+        for token in tokens.iter() {
+            let Token::Plural { arg, cases, .. } = token else {
+                to_pieces(
+                    std::slice::from_ref(token),
+                    &args,
+                    &numeric,
+                    Style::Apple,
+                    None,
+                    &mut pieces,
+                );
+                continue;
+            };
+            if variables.contains_key(arg) {
+                return Err(Error::MultiplePlurals {
+                    key: key.to_owned(),
+                });
+            }
+            let index = args
+                .iter()
+                .position(|a| a == arg)
+                .expect("argument was collected")
+                + 1;
+            pieces.push(Piece::Placeholder {
+                name: arg.clone(),
+                spec: format!("%{}$#@{}@", index, arg),
+            });
+            let mut variable = Dictionary::new();
+            variable.insert(SPEC_TYPE_KEY.to_owned(), PLURAL_RULE_TYPE.into());
+            variable.insert(VALUE_TYPE_KEY.to_owned(), "d".into());
+            for case in cases.iter() {
+                let mut case_pieces = Vec::new();
+                to_pieces(
+                    &case.tokens,
+                    &args,
+                    &numeric,
+                    Style::Apple,
+                    Some(arg),
+                    &mut case_pieces,
+                );
+                variable.insert(
+                    plural_category_name(case.key).to_owned(),
+                    join(&case_pieces).into(),
+                );
+            }
+            variables.insert(arg.clone(), variable.into());
+        }
+        entry.insert(FORMAT_KEY.to_owned(), join(&pieces).into());
+        entry.extend(variables);
+        root.insert(key.to_owned(), entry.into());
+    }
+    let mut out = Vec::new();
+    Value::Dictionary(root).to_writer_xml(&mut out)?;
+    let mut out = String::from_utf8(out).expect("plist XML is UTF-8");
+    out.push('\n');
+    Ok(out)
+}
+
+/// Reads an Apple `.strings` file into a catalog.
+///
+/// A comment directly before an entry becomes the description of its key.
+pub fn from_strings(src: &str) -> Result<Catalog, Error> {
+    let mut parser = StringsParser {
+        chars: src.chars().peekable(),
+        line: 1,
+        comment: None,
+    };
+    let mut catalog = Catalog::default();
+    while let Some(key) = parser.next_string()? {
+        // Xcode writes this placeholder when a string has no comment.
+        if let Some(description) = parser
+            .comment
+            .take()
+            .filter(|c| c != "No comment provided by engineer.")
+        {
+            catalog.set_description(key.clone(), description);
+        }
+        let value = match parser.next_significant() {
+            Some('=') => {
+                let value = parser
+                    .next_string()?
+                    .ok_or_else(|| parser.error("expected a value"))?;
+                match parser.next_significant() {
+                    Some(';') => value,
+                    _ => return Err(parser.error("expected `;`")),
+                }
+            }
+            Some(';') => key.clone(),
+            _ => return Err(parser.error("expected `=` or `;`")),
+        };
+        let message = format_message(&value);
+        catalog.insert(key, message);
+        parser.comment = None;
+    }
+    Ok(catalog)
+}
+
+struct StringsParser<'a> {
+    chars: std::iter::Peekable<std::str::Chars<'a>>,
+    line: usize,
+    /// The last comment skipped by [`StringsParser::next_significant`].
+    comment: Option<String>,
+}
+
+impl StringsParser<'_> {
+    fn error(&self, message: &str) -> Error {
+        Error::StringsSyntax {
+            line: self.line,
+            message: message.to_owned(),
+        }
+    }
+
+    fn next_char(&mut self) -> Option<char> {
+        let c = self.chars.next();
+        if c == Some('\n') {
+            self.line += 1;
+        }
+        c
+    }
+
+    /// Skips whitespace and comments, returning the next character.
+    fn next_significant(&mut self) -> Option<char> {
+        loop {
+            let c = self.next_char()?;
+            match c {
+                c if c.is_whitespace() || c == '\u{feff}' => {}
+                '/' if self.chars.peek() == Some(&'*') => {
+                    self.next_char();
+                    let mut comment = String::new();
+                    while let Some(c) = self.next_char() {
+                        if c == '/' && comment.ends_with('*') {
+                            comment.pop();
+                            break;
+                        }
+                        comment.push(c);
+                    }
+                    self.comment = Some(comment.trim().to_owned());
+                }
+                '/' if self.chars.peek() == Some(&'/') => {
+                    self.next_char();
+                    let mut comment = String::new();
+                    while let Some(c) = self.next_char().filter(|&c| c != '\n') {
+                        comment.push(c);
+                    }
+                    self.comment = Some(comment.trim().to_owned());
+                }
+                c => return Some(c),
+            }
+        }
+    }
+
+    /// Reads a quoted or unquoted string, or returns `None` at the end of input.
+    fn next_string(&mut self) -> Result<Option<String>, Error> {
+        let mut out = String::new();
+        match self.next_significant() {
+            None => return Ok(None),
+            Some('"') => loop {
+                match self.next_char() {
+                    Some('"') => break,
+                    Some('\\') => match self.next_char() {
+                        Some('n') => out.push('\n'),
+                        Some('t') => out.push('\t'),
+                        Some('r') => out.push('\r'),
+                        Some('U' | 'u') => {
+                            let hex: String = (0..4).filter_map(|_| self.next_char()).collect();
+                            let c = u32::from_str_radix(&hex, 16)
+                                .ok()
+                                .and_then(char::from_u32)
+                                .ok_or_else(|| self.error("invalid unicode escape"))?;
+                            out.push(c);
+                        }
+                        Some(c) => out.push(c),
+                        None => return Err(self.error("unterminated string")),
+                    },
+                    Some(c) => out.push(c),
+                    None => return Err(self.error("unterminated string")),
+                }
+            },
+            Some(c) if c.is_alphanumeric() || "_.$:/-".contains(c) => {
+                out.push(c);
+                while let Some(&c) = self.chars.peek() {
+                    if !(c.is_alphanumeric() || "_.$:/-".contains(c)) {
+                        break;
+                    }
+                    out.push(c);
+                    self.next_char();
+                }
+            }
+            Some(_) => return Err(self.error("expected a string")),
+        }
+        Ok(Some(out))
+    }
+}
+
+/// Converts a format string with positional placeholders into a message.
+fn format_message(value: &str) -> String {
+    let mut tokens = Tokens::new();
+    let mut next = 0;
+    for segment in parse_format(value) {
+        match segment {
+            Segment::Text(text) => push_content(&mut tokens, &text),
+            Segment::Placeholder { index, .. } | Segment::Variable { index, .. } => {
+                tokens.push(Token::PlainArg {
+                    arg: positional_name(resolve_index(index, &mut next)),
+                })
+            }
+        }
+    }
+    to_source(&tokens)
+}
+
+/// Reads an Apple `.stringsdict` file into a catalog.
+pub fn from_stringsdict(src: &str) -> Result<Catalog, Error> {
+    let root: Dictionary = plist::from_bytes(src.as_bytes())?;
+    let mut catalog = Catalog::default();
+    for (key, entry) in root {
+        let entry = entry
+            .into_dictionary()
+            .ok_or_else(|| invalid(&key, "expected a dictionary"))?;
+        let format = entry
+            .get(FORMAT_KEY)
+            .and_then(Value::as_string)
+            .ok_or_else(|| invalid(&key, "missing NSStringLocalizedFormatKey"))?;
+
+        let segments: Vec<_> = {
+            let mut next = 0;
+            parse_format(format)
+                .into_iter()
+                .map(|segment| {
+                    let index = match &segment {
+                        Segment::Placeholder { index, .. } | Segment::Variable { index, .. } => {
+                            resolve_index(*index, &mut next)
+                        }
+                        Segment::Text(_) => 0,
+                    };
+                    (segment, index)
+                })
+                .collect()
+        };
+        let names: BTreeMap<usize, &str> = segments
+            .iter()
+            .filter_map(|(segment, index)| match segment {
+                Segment::Variable { name, .. } => Some((*index, name.as_str())),
+                _ => None,
+            })
+            .collect();
+        let name_for = |index: usize| {
+            names
+                .get(&index)
+                .map(|name| name.to_string())
+                .unwrap_or_else(|| positional_name(index))
+        };
+
+        let mut tokens = Tokens::new();
+        for (segment, index) in &segments {
+            match segment {
+                Segment::Text(text) => push_content(&mut tokens, text),
+                Segment::Placeholder { .. } => tokens.push(Token::PlainArg {
+                    arg: name_for(*index),
+                }),
+                Segment::Variable { name, .. } => {
+                    check_arg_name(&key, name)?;
+                    let variable = entry
+                        .get(name)
+                        .and_then(Value::as_dictionary)
+                        .ok_or_else(|| invalid(&key, &format!("missing variable {:?}", name)))?;
+                    tokens.push(plural(&key, name, *index, variable, &name_for)?);
+                }
+            }
+        }
+        catalog.insert(key, to_source(&tokens));
+    }
+    Ok(catalog)
+}
+
+fn plural<'a>(
+    key: &str,
+    name: &str,
+    index: usize,
+    variable: &Dictionary,
+    name_for: &impl Fn(usize) -> String,
+) -> Result<Token<'a, 'a, String>, Error> {
+    match variable.get(SPEC_TYPE_KEY).and_then(Value::as_string) {
+        Some(PLURAL_RULE_TYPE) => {}
+        _ => return Err(invalid(key, &format!("{:?} is not a plural rule", name))),
+    }
+    let mut cases = Vec::new();
+    for (category, case) in variable.iter() {
+        let Some(category) = PluralCategory::get_for_cldr_string(category) else {
+            continue;
+        };
+        let case = case
+            .as_string()
+            .ok_or_else(|| invalid(key, "plural cases must be strings"))?;
+        let mut tokens = Tokens::new();
+        let mut next = 0;
+        for segment in parse_format(case) {
+            match segment {
+                Segment::Text(text) => push_content(&mut tokens, &text),
+                Segment::Placeholder { index: Some(i), .. } if i == index => {
+                    tokens.push(Token::Octothorpe {})
+                }
+                ref s @ Segment::Placeholder { index: None, .. } if s.is_integer() => {
+                    tokens.push(Token::Octothorpe {})
+                }
+                Segment::Placeholder { index, .. } | Segment::Variable { index, .. } => tokens
+                    .push(Token::PlainArg {
+                        arg: name_for(resolve_index(index, &mut next)),
+                    }),
+            }
+        }
+        cases.push(PluralCase {
+            key: category,
+            tokens: tokens.into(),
+        });
+    }
+    Ok(Token::Plural {
+        arg: name.to_owned(),
+        cases: cases.into(),
+        plural_offset: None,
+    })
+}
+
+fn invalid(key: &str, message: &str) -> Error {
+    Error::InvalidEntry {
+        key: key.to_owned(),
+        message: message.to_owned(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn catalog(messages: &[(&str, &str)]) -> Catalog {
+        let mut catalog = Catalog::default();
+        for (key, message) in messages {
+            catalog.insert(*key, *message);
+        }
+        catalog
+    }
+
+    #[test]
+    fn export_strings() {
+        let mut catalog = catalog(&[
+            ("greeting", "Hello {name}, you are \"{age}\" at 100%"),
+            ("plain", "100% done\n"),
+            ("items", "{count, plural, one {# item} other {# items}}"),
+        ]);
+        catalog.set_description("plain", "Shown when */ done");
+        assert_eq!(
+            to_strings(&catalog).unwrap(),
+            "\"greeting\" = \"Hello %1$@, you are \\\"%2$@\\\" at 100%%\";\n\
+             /* Shown when * / done */\n\
+             \"plain\" = \"100%% done\\n\";\n"
+        );
+    }
+
+    #[test]
+    fn export_stringsdict() {
+        let catalog = catalog(&[
+            ("plain", "No plurals here"),
+            (
+                "items",
+                "{name} has {count, plural, one {# item} other {# items}} ({count})",
+            ),
+        ]);
+        assert_eq!(
+            to_stringsdict(&catalog).unwrap(),
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+	<key>items</key>
+	<dict>
+		<key>NSStringLocalizedFormatKey</key>
+		<string>%1$@ has %2$#@count@ (%2$d)</string>
+		<key>count</key>
+		<dict>
+			<key>NSStringFormatSpecTypeKey</key>
+			<string>NSStringPluralRuleType</string>
+			<key>NSStringFormatValueTypeKey</key>
+			<string>d</string>
+			<key>one</key>
+			<string>%2$d item</string>
+			<key>other</key>
+			<string>%2$d items</string>
+		</dict>
+	</dict>
+</dict>
+</plist>
+"#
+        );
+    }
+
+    #[test]
+    fn import_strings() {
+        let src = r#"
+/* A comment */
+"greeting" = "Hello %@, you have %2$d {new} messages";
+// Another comment
+unquoted = "100%% \"done\"\U263A";
+"same";
+"#;
+        let mut expected = catalog(&[
+            (
+                "greeting",
+                "Hello {arg1}, you have {arg2} '{'new'}' messages",
+            ),
+            ("same", "same"),
+            ("unquoted", "100% \"done\"☺"),
+        ]);
+        expected.set_description("greeting", "A comment");
+        expected.set_description("unquoted", "Another comment");
+        assert_eq!(from_strings(src).unwrap(), expected);
+        assert!(matches!(
+            from_strings("\"a\" = \"b\"\n\"c\" = \"d\";"),
+            Err(Error::StringsSyntax { line: 2, .. })
+        ));
+    }
+
+    #[test]
+    fn roundtrip() {
+        let catalog = catalog(&[
+            ("greeting", "Hello {arg1}, it''s 100% done!"),
+            ("discount", "Save 50% discount"),
+            (
+                "items",
+                "{arg1} has {count, plural, one {# item} other {# items}}",
+            ),
+            (
+                "files",
+                "{files, plural, zero {No files} one {One file} other {# files}} in {folder, plural, other {# folders}}",
+            ),
+        ]);
+        let mut imported = from_strings(&to_strings(&catalog).unwrap()).unwrap();
+        imported.extend(from_stringsdict(&to_stringsdict(&catalog).unwrap()).unwrap());
+        assert_eq!(imported, catalog);
+    }
+}
@@ -0,0 +1,149 @@
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+
+use crate::Error;
+
+/// A message catalog for a single locale.
+///
+/// Nested keys are flattened into dotted paths, so `{"nested": {"keys": "..."}}`
+/// is stored under `nested.keys`. The values are MessageFormat 1 sources.
+///
+/// `@key` metadata entries are kept in [`Catalog::metadata`] under the path of
+/// the key they describe.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Catalog {
+    pub messages: BTreeMap<String, String>,
+    pub metadata: BTreeMap<String, Map<String, Value>>,
+}
+
+impl Catalog {
+    /// Reads a catalog in the JSON format used by `load_locales!`.
+    pub fn from_json(src: &str) -> Result<Self, Error> {
+        let keys: Map<String, Value> = serde_json::from_str(src)?;
+        let mut catalog = Catalog::default();
+        catalog.flatten("", keys)?;
+        Ok(catalog)
+    }
+
+    fn flatten(&mut self, prefix: &str, keys: Map<String, Value>) -> Result<(), Error> {
+        for (key, item) in keys {
+            let (key, is_metadata) = match key.strip_prefix('@') {
+                Some(key) => (key, true),
+                None => (key.as_str(), false),
+            };
+            let path = format!("{}{}", prefix, key);
+            match item {
+                Value::Object(metadata) if is_metadata => {
+                    self.metadata.insert(path, metadata);
+                }
+                Value::String(message) if !is_metadata => {
+                    self.messages.insert(path, message);
+                }
+                Value::Object(keys) if !is_metadata => self.flatten(&format!("{}.", path), keys)?,
+                _ => {
+                    return Err(Error::InvalidEntry {
+                        key: path,
+                        message: "expected a string or an object".to_owned(),
+                    })
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Writes the catalog in the JSON format used by `load_locales!`,
+    /// turning dotted paths back into nested objects.
+    pub fn to_json(&self) -> Result<String, Error> {
+        let mut root = Map::new();
+        let entries = self
+            .messages
+            .iter()
+            .map(|(path, message)| (path, false, Value::String(message.clone())))
+            .chain(
+                self.metadata
+                    .iter()
+                    .map(|(path, metadata)| (path, true, Value::Object(metadata.clone()))),
+            );
+        for (path, is_metadata, value) in entries {
+            let mut keys = &mut root;
+            let mut segments = path.split('.').peekable();
+            while let Some(segment) = segments.next() {
+                if segments.peek().is_none() {
+                    let segment = if is_metadata {
+                        format!("@{}", segment)
+                    } else {
+                        segment.to_owned()
+                    };
+                    if keys.insert(segment, value).is_some() {
+                        return Err(Error::KeyConflict { key: path.clone() });
+                    }
+                    break;
+                }
+                let item = keys
+                    .entry(segment.to_owned())
+                    .or_insert_with(|| Value::Object(Map::new()));
+                keys = match item {
+                    Value::Object(keys) => keys,
+                    _ => return Err(Error::KeyConflict { key: path.clone() }),
+                };
+            }
+        }
+        let mut out = serde_json::to_string_pretty(&root)?;
+        out.push('\n');
+        Ok(out)
+    }
+
+    pub fn insert(&mut self, path: impl Into<String>, message: impl Into<String>) {
+        self.messages.insert(path.into(), message.into());
+    }
+
+    /// Adds every message and metadata entry from `other`, replacing existing
+    /// entries with the same path.
+    pub fn extend(&mut self, other: Catalog) {
+        self.messages.extend(other.messages);
+        self.metadata.extend(other.metadata);
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.messages.iter().map(|(k, v)| (k.as_str(), v.as_str()))
+    }
+
+    /// The translator description of a key, from its `@key` metadata.
+    pub fn description(&self, path: &str) -> Option<&str> {
+        self.metadata.get(path)?.get("description")?.as_str()
+    }
+
+    pub fn set_description(&mut self, path: impl Into<String>, description: impl Into<String>) {
+        self.metadata
+            .entry(path.into())
+            .or_default()
+            .insert("description".to_owned(), description.into().into());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn json_roundtrip() {
+        let src = r#"{
+  "@message": {
+    "description": "Greeting",
+    "maxLength": 40
+  },
+  "message": "Hello",
+  "nested": {
+    "@keys": {
+      "translate": false
+    },
+    "keys": "Nested"
+  }
+}
+"#;
+        let catalog = Catalog::from_json(src).unwrap();
+        assert_eq!(catalog.messages["nested.keys"], "Nested");
+        assert_eq!(catalog.description("message"), Some("Greeting"));
+        assert_eq!(catalog.to_json().unwrap(), src);
+    }
+}
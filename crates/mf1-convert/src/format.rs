@@ -0,0 +1,363 @@
+//! Shared handling of printf-style format strings, as used by both Android and
+//! Apple string resources.
+
+use mf1_parser::{parse, Token};
+
+use crate::Error;
+
+pub(crate) type Tokens<'a> = Vec<Token<'a, 'a, String>>;
+
+/// How argument placeholders are spelled by a platform.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Style {
+    /// `%1$s` for text and `%1$d` for numbers.
+    Android,
+    /// `%1$@` for text and `%1$d` for numbers.
+    Apple,
+}
+
+impl Style {
+    fn string_conversion(self) -> char {
+        match self {
+            Style::Android => 's',
+            Style::Apple => '@',
+        }
+    }
+}
+
+pub(crate) fn parse_message<'a>(key: &str, src: &'a str) -> Result<Tokens<'a>, Error> {
+    parse(src).map_err(|(message, span)| Error::ParseKeyErr {
+        key: key.to_owned(),
+        src: src.to_owned(),
+        message,
+        span,
+    })
+}
+
+/// Checks that a message only uses constructs with a native equivalent, and
+/// returns the number of `plural` arguments it contains.
+pub(crate) fn check_supported(key: &str, tokens: &[Token<String>]) -> Result<usize, Error> {
+    let mut plurals = 0;
+    for token in tokens {
+        match token {
+            Token::Content { .. } | Token::PlainArg { .. } | Token::Octothorpe {} => {}
+            Token::FunctionArg { arg, .. } => return Err(unsupported(key, arg, "function")),
+            Token::Select { arg, .. } => return Err(unsupported(key, arg, "select")),
+            Token::SelectOrdinal { arg, .. } => return Err(unsupported(key, arg, "selectordinal")),
+            Token::Plural {
+                arg,
+                plural_offset: Some(_),
+                ..
+            } => return Err(unsupported(key, arg, "plural with an offset")),
+            Token::Plural { cases, .. } => {
+                for case in cases.iter() {
+                    if check_supported(key, &case.tokens)? > 0 {
+                        return Err(Error::MultiplePlurals {
+                            key: key.to_owned(),
+                        });
+                    }
+                }
+                plurals += 1;
+            }
+        }
+    }
+    Ok(plurals)
+}
+
+fn unsupported(key: &str, arg: &str, kind: &'static str) -> Error {
+    Error::Unsupported {
+        key: key.to_owned(),
+        arg: arg.to_owned(),
+        kind,
+    }
+}
+
+/// Collects argument names in order of first appearance.
+pub(crate) fn arg_names(tokens: &[Token<String>]) -> Vec<String> {
+    fn add(names: &mut Vec<String>, arg: &String) {
+        if !names.contains(arg) {
+            names.push(arg.clone())
+        }
+    }
+    fn collect(tokens: &[Token<String>], names: &mut Vec<String>) {
+        for token in tokens {
+            match token {
+                Token::Content { .. } | Token::Octothorpe {} => {}
+                Token::PlainArg { arg } | Token::FunctionArg { arg, .. } => add(names, arg),
+                Token::Plural { arg, cases, .. } | Token::SelectOrdinal { arg, cases, .. } => {
+                    add(names, arg);
+                    for case in cases.iter() {
+                        collect(&case.tokens, names);
+                    }
+                }
+                Token::Select { arg, cases, .. } => {
+                    add(names, arg);
+                    for case in cases.iter() {
+                        collect(&case.tokens, names);
+                    }
+                }
+            }
+        }
+    }
+    let mut names = Vec::new();
+    collect(tokens, &mut names);
+    names
+}
+
+/// A piece of an exported format string.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Piece {
+    Text(String),
+    Placeholder { name: String, spec: String },
+}
+
+/// Turns plain message tokens into format string pieces.
+///
+/// `plural` is the argument of the enclosing plural, which `#` refers to.
+/// Arguments in `numeric` are formatted as integers rather than text.
+pub(crate) fn to_pieces(
+    tokens: &[Token<String>],
+    args: &[String],
+    numeric: &[&str],
+    style: Style,
+    plural: Option<&str>,
+    pieces: &mut Vec<Piece>,
+) {
+    let placeholder = |arg: &str| {
+        let index = args
+            .iter()
+            .position(|a| a == arg)
+            .expect("argument was collected")
+            + 1;
+        let conversion = if numeric.contains(&arg) {
+            'd'
+        } else {
+            style.string_conversion()
+        };
+        Piece::Placeholder {
+            name: arg.to_owned(),
+            spec: format!("%{}${}", index, conversion),
+        }
+    };
+    for token in tokens {
+        match token {
+            Token::Content { value } => {
+                // Imports always read `%` as a specifier, so it is escaped
+                // even in messages without arguments.
+                let value = value.replace('%', "%%");
+                match pieces.last_mut() {
+                    Some(Piece::Text(text)) => text.push_str(&value),
+                    _ => pieces.push(Piece::Text(value)),
+                }
+            }
+            Token::PlainArg { arg } => pieces.push(placeholder(arg)),
+            Token::Octothorpe {} => match plural {
+                Some(arg) => pieces.push(placeholder(arg)),
+                None => match pieces.last_mut() {
+                    Some(Piece::Text(text)) => text.push('#'),
+                    _ => pieces.push(Piece::Text("#".to_owned())),
+                },
+            },
+            // Rejected by `check_supported`, and plurals are split out by the caller.
+            Token::FunctionArg { .. }
+            | Token::Plural { .. }
+            | Token::SelectOrdinal { .. }
+            | Token::Select { .. } => unreachable!("unsupported token in format string"),
+        }
+    }
+}
+
+/// A segment of an imported format string.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Segment {
+    Text(String),
+    /// A placeholder such as `%1$s` or `%d`.
+    Placeholder {
+        index: Option<usize>,
+        conversion: char,
+    },
+    /// A `.stringsdict` variable such as `%#@count@`.
+    Variable {
+        index: Option<usize>,
+        name: String,
+    },
+}
+
+impl Segment {
+    pub(crate) fn is_integer(&self) -> bool {
+        matches!(
+            self,
+            Segment::Placeholder {
+                conversion: 'd' | 'i' | 'u' | 'D' | 'U' | 'x' | 'X' | 'o' | 'O',
+                ..
+            }
+        )
+    }
+}
+
+/// Splits a printf-style format string into text and placeholders.
+///
+/// Anything that does not form a valid specifier is kept as text.
+pub(crate) fn parse_format(src: &str) -> Vec<Segment> {
+    let mut segments = Vec::new();
+    let mut text = String::new();
+    let mut rest = src;
+    while let Some(start) = rest.find('%') {
+        text.push_str(&rest[..start]);
+        rest = &rest[start..];
+        if let Some(after) = rest.strip_prefix("%%") {
+            text.push('%');
+            rest = after;
+            continue;
+        }
+        match parse_specifier(&rest[1..]) {
+            Some((segment, len)) => {
+                if !text.is_empty() {
+                    segments.push(Segment::Text(std::mem::take(&mut text)));
+                }
+                segments.push(segment);
+                rest = &rest[1 + len..];
+            }
+            None => {
+                text.push('%');
+                rest = &rest[1..];
+            }
+        }
+    }
+    text.push_str(rest);
+    if !text.is_empty() {
+        segments.push(Segment::Text(text));
+    }
+    segments
+}
+
+/// Parses the part of a specifier after the `%`, returning its length.
+fn parse_specifier(src: &str) -> Option<(Segment, usize)> {
+    let bytes = src.as_bytes();
+    let mut i = 0;
+    let digits = bytes.iter().take_while(|b| b.is_ascii_digit()).count();
+    let mut index = None;
+    if digits > 0 && bytes.get(digits) == Some(&b'$') {
+        index = src[..digits].parse().ok();
+        i = digits + 1;
+    }
+    if src[i..].starts_with("#@") {
+        let name_start = i + 2;
+        let name_len = src[name_start..].find('@')?;
+        let name = src[name_start..name_start + name_len].to_owned();
+        return Some((Segment::Variable { index, name }, name_start + name_len + 1));
+    }
+    while matches!(bytes.get(i), Some(b'-' | b'+' | b' ' | b'0' | b'#' | b'\'')) {
+        i += 1;
+    }
+    while matches!(bytes.get(i), Some(b) if b.is_ascii_digit() || *b == b'*') {
+        i += 1;
+    }
+    if bytes.get(i) == Some(&b'.') {
+        i += 1;
+        while matches!(bytes.get(i), Some(b) if b.is_ascii_digit()) {
+            i += 1;
+        }
+    }
+    while matches!(
+        bytes.get(i),
+        Some(b'h' | b'l' | b'q' | b'L' | b'z' | b't' | b'j')
+    ) {
+        i += 1;
+    }
+    match bytes.get(i) {
+        Some(
+            c @ (b'@' | b'd' | b'D' | b'i' | b'u' | b'U' | b'x' | b'X' | b'o' | b'O' | b'f' | b'F'
+            | b'e' | b'E' | b'g' | b'G' | b'c' | b'C' | b's' | b'S' | b'p' | b'a' | b'A'),
+        ) => Some((
+            Segment::Placeholder {
+                index,
+                conversion: *c as char,
+            },
+            i + 1,
+        )),
+        _ => None,
+    }
+}
+
+/// Assigns 1-based argument positions, numbering unpositioned specifiers in order.
+pub(crate) fn resolve_index(index: Option<usize>, next: &mut usize) -> usize {
+    match index {
+        Some(index) => index,
+        None => {
+            *next += 1;
+            *next
+        }
+    }
+}
+
+/// The argument name used for a positional specifier without a known name.
+pub(crate) fn positional_name(index: usize) -> String {
+    format!("arg{}", index)
+}
+
+pub(crate) fn push_content(tokens: &mut Tokens<'_>, text: &str) {
+    if text.is_empty() {
+        return;
+    }
+    match tokens.last_mut() {
+        Some(Token::Content { value }) => value.push_str(text),
+        _ => tokens.push(Token::Content {
+            value: text.to_owned(),
+        }),
+    }
+}
+
+/// Checks that an imported argument name can be used in MessageFormat.
+pub(crate) fn check_arg_name(key: &str, name: &str) -> Result<(), Error> {
+    let mut chars = name.chars();
+    let valid = chars.next().is_some_and(char::is_alphanumeric)
+        && chars.all(|c| c.is_alphanumeric() || c == '_');
+    if valid {
+        Ok(())
+    } else {
+        Err(Error::InvalidEntry {
+            key: key.to_owned(),
+            message: format!("{:?} is not a valid argument name", name),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_specifiers() {
+        assert_eq!(
+            parse_format("%1$s and %@, 100%% %lld %5.2f %#@count@ %"),
+            vec![
+                Segment::Placeholder {
+                    index: Some(1),
+                    conversion: 's'
+                },
+                Segment::Text(" and ".to_owned()),
+                Segment::Placeholder {
+                    index: None,
+                    conversion: '@'
+                },
+                Segment::Text(", 100% ".to_owned()),
+                Segment::Placeholder {
+                    index: None,
+                    conversion: 'd'
+                },
+                Segment::Text(" ".to_owned()),
+                Segment::Placeholder {
+                    index: None,
+                    conversion: 'f'
+                },
+                Segment::Text(" ".to_owned()),
+                Segment::Variable {
+                    index: None,
+                    name: "count".to_owned()
+                },
+                Segment::Text(" %".to_owned()),
+            ]
+        );
+    }
+}
@@ -0,0 +1,52 @@
+//! Conversion between `mf1` JSON catalogs and native mobile string resources.
+//!
+//! - [`android`] reads and writes Android `strings.xml` files, including `<plurals>`.
+//! - [`apple`] reads and writes Apple `.strings` and `.stringsdict` files.
+//!
+//! Placeholders are mapped to positional format specifiers (`%1$s` on Android,
+//! `%1$@` on Apple platforms), numbered in order of first appearance in the
+//! message. A message containing a `plural` argument is turned into the native
+//! plural structure, with the text around the argument copied into every case.
+//! Neither platform has an equivalent of `select` or `selectordinal`, so those
+//! are reported as errors.
+
+use mf1_parser::LexerSpan;
+use thiserror::Error;
+
+pub mod android;
+pub mod apple;
+mod catalog;
+mod format;
+
+pub use catalog::Catalog;
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("Error parsing JSON catalog: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("Error parsing XML: {0}")]
+    Xml(#[from] quick_xml::Error),
+    #[error("Error reading property list: {0}")]
+    Plist(#[from] plist::Error),
+    #[error("Syntax error in .strings file on line {line}: {message}")]
+    StringsSyntax { line: usize, message: String },
+    #[error("Parsing of key {key} failed: {message} ({span:?})")]
+    ParseKeyErr {
+        key: String,
+        src: String,
+        message: String,
+        span: LexerSpan,
+    },
+    #[error("Key {key} uses {arg:?} as a {kind}, which has no native equivalent")]
+    Unsupported {
+        key: String,
+        arg: String,
+        kind: &'static str,
+    },
+    #[error("Key {key} has more than one plural argument, which is not supported here")]
+    MultiplePlurals { key: String },
+    #[error("Key {key} is both a message and a namespace")]
+    KeyConflict { key: String },
+    #[error("Invalid entry {key}: {message}")]
+    InvalidEntry { key: String, message: String },
+}
@@ -1,21 +1,122 @@
 use proc_macro2::TokenStream as TokenStream2;
 use t_macro::OutputType;
 
-mod load_locales;
+mod select_derive;
 mod t_macro;
 
+/// Generates `Locale` and the messages of each locale, from the locales listed
+/// in `[package.metadata.mf1]`.
+///
+/// `load_locales!(codegen = "compact")` generates a string table per locale
+/// instead of a const struct, whatever `codegen` is set to in the config.
 #[proc_macro]
-pub fn load_locales(_tokens: proc_macro::TokenStream) -> proc_macro::TokenStream {
-    match load_locales::load_locales() {
+pub fn load_locales(tokens: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let generate = || {
+        let mut builder = mf1_build::Builder::new()
+            .ambient_locale(cfg!(feature = "ambient-locale"))
+            .serde(cfg!(feature = "serde"));
+        if let Some(codegen) = mf1_build::parse_args(tokens.into())? {
+            builder = builder.codegen(codegen);
+        }
+        builder.generate()
+    };
+    match generate() {
         Ok(ts) => ts.into(),
-        Err(err) => err.into(),
+        Err(err) => err.to_compile_error().into(),
     }
 }
+/// Formats a message as a string: `t_l_string!(locale, nested.key, arg = value)`.
+///
+/// Keys that are not valid identifiers can be written as string literals,
+/// as in `t_l_string!(locale, nested."sign-in")`. Keywords and numbers can be
+/// written as they are, as in `t_l_string!(locale, errors.404)`.
 #[proc_macro]
 pub fn t_l_string(tokens: proc_macro::TokenStream) -> proc_macro::TokenStream {
     let tokens_2 = TokenStream2::from(tokens);
-    match t_macro::t_macro(tokens_2, OutputType::String) {
+    match t_macro::t_macro(tokens_2, OutputType::String, false) {
+        Ok(ts) => proc_macro::TokenStream::from(ts),
+        Err(err) => err.into(),
+    }
+}
+
+/// Formats a message as an `impl Display`, without allocating:
+/// `t_l_display!(locale, nested.key, arg = value)`.
+///
+/// Arguments are borrowed, so values given as `arg = value` only live until the
+/// end of the statement using the macro.
+#[proc_macro]
+pub fn t_l_display(tokens: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let tokens_2 = TokenStream2::from(tokens);
+    match t_macro::t_macro(tokens_2, OutputType::Display, false) {
+        Ok(ts) => proc_macro::TokenStream::from(ts),
+        Err(err) => err.into(),
+    }
+}
+
+/// Writes a message into a `fmt::Write` or `io::Write`, without allocating:
+/// `t_l_write!(writer, locale, nested.key, arg = value)`.
+///
+/// Evaluates to the result of `write!`.
+#[proc_macro]
+pub fn t_l_write(tokens: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let tokens_2 = TokenStream2::from(tokens);
+    match t_macro::t_macro(tokens_2, OutputType::Write, false) {
+        Ok(ts) => proc_macro::TokenStream::from(ts),
+        Err(err) => err.into(),
+    }
+}
+
+/// Formats a message as a string in the current locale of the thread:
+/// `t_string!(nested.key, arg = value)`.
+///
+/// Takes the same keys and arguments as `t_l_string!`, and expects the `Locale`
+/// generated by `load_locales!` to be in scope.
+#[cfg(feature = "ambient-locale")]
+#[proc_macro]
+pub fn t_string(tokens: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let tokens_2 = TokenStream2::from(tokens);
+    match t_macro::t_macro(tokens_2, OutputType::String, true) {
+        Ok(ts) => proc_macro::TokenStream::from(ts),
+        Err(err) => err.into(),
+    }
+}
+
+/// Formats a message as an `impl Display` in the current locale of the thread:
+/// `t_display!(nested.key, arg = value)`.
+///
+/// The locale is read when the macro is evaluated, not when the message is
+/// displayed.
+#[cfg(feature = "ambient-locale")]
+#[proc_macro]
+pub fn t_display(tokens: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let tokens_2 = TokenStream2::from(tokens);
+    match t_macro::t_macro(tokens_2, OutputType::Display, true) {
+        Ok(ts) => proc_macro::TokenStream::from(ts),
+        Err(err) => err.into(),
+    }
+}
+
+/// Writes a message in the current locale of the thread into a `fmt::Write` or
+/// `io::Write`: `t_write!(writer, nested.key, arg = value)`.
+#[cfg(feature = "ambient-locale")]
+#[proc_macro]
+pub fn t_write(tokens: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let tokens_2 = TokenStream2::from(tokens);
+    match t_macro::t_macro(tokens_2, OutputType::Write, true) {
         Ok(ts) => proc_macro::TokenStream::from(ts),
         Err(err) => err.into(),
     }
 }
+
+/// Derives `mf1::Select` for an enum, so it can be passed as a `select` argument.
+///
+/// Each variant's key is its name in `snake_case`. Use `#[mf1(key = "...")]`
+/// on a variant to choose another key.
+#[proc_macro_derive(Mf1Select, attributes(mf1))]
+pub fn derive_select(tokens: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let input = syn::parse_macro_input!(tokens as syn::DeriveInput);
+    match select_derive::derive_select(input) {
+        Ok(ts) => ts.into(),
+        Err(err) => err.to_compile_error().into(),
+    }
+}
@@ -0,0 +1,67 @@
+use convert_case::{Case, Casing};
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{Data, DeriveInput, Fields, LitStr};
+
+/// Implements `mf1::Select` for an enum of unit variants.
+///
+/// Each variant's key is its name in `snake_case`, unless it is given with
+/// `#[mf1(key = "...")]`.
+pub fn derive_select(input: DeriveInput) -> syn::Result<TokenStream> {
+    let Data::Enum(data) = &input.data else {
+        return Err(syn::Error::new_spanned(
+            &input.ident,
+            "Mf1Select can only be derived for enums",
+        ));
+    };
+    let mut variants = Vec::new();
+    let mut keys = Vec::new();
+    for variant in data.variants.iter() {
+        if !matches!(variant.fields, Fields::Unit) {
+            return Err(syn::Error::new_spanned(
+                variant,
+                "Mf1Select variants can't have fields",
+            ));
+        }
+        let mut key = variant.ident.to_string().to_case(Case::Snake);
+        for attr in variant.attrs.iter().filter(|a| a.path().is_ident("mf1")) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("key") {
+                    key = meta.value()?.parse::<LitStr>()?.value();
+                    Ok(())
+                } else {
+                    Err(meta.error("expected `key`"))
+                }
+            })?;
+        }
+        if let Some(i) = keys.iter().position(|k| *k == key) {
+            let first = &data.variants[i].ident;
+            return Err(syn::Error::new_spanned(
+                variant,
+                format!("variant has the same key {:?} as {}", key, first),
+            ));
+        }
+        variants.push(&variant.ident);
+        keys.push(key);
+    }
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics mf1::Select for #ident #ty_generics #where_clause {
+            const KEYS: &'static [&'static str] = &[#(#keys,)*];
+
+            fn select_key(&self) -> &'static str {
+                match *self {
+                    #(Self::#variants => #keys,)*
+                }
+            }
+
+            fn from_select_key(key: &str) -> Option<&'static Self> {
+                match key {
+                    #(#keys => Some(&Self::#variants),)*
+                    _ => None,
+                }
+            }
+        }
+    })
+}
@@ -1,12 +1,22 @@
 use proc_macro2::{Span, TokenStream};
 use quote::{quote, TokenStreamExt};
 
-use syn::{token, Expr, Ident};
+use syn::{parse::Parser, Ident};
 use thiserror::Error;
 
+use mf1_build::ident::mangle;
+use mf1_build::macro_input::{InterpolatedValue, ParsedInput, WriteInput};
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum OutputType {
+    /// A `Cow<'static, str>`.
     String,
+    /// An `impl Display` borrowing the arguments, which formats the message
+    /// without allocating.
+    Display,
+    /// Writes the message into the writer given as the first argument, which
+    /// can be a `fmt::Write` or an `io::Write`.
+    Write,
 }
 
 #[derive(Debug, Error)]
@@ -24,76 +34,56 @@ impl From<Error> for proc_macro::TokenStream {
     }
 }
 
-pub struct ParsedInput {
-    pub context: Expr,
-    pub keys: Vec<Ident>,
-    pub interpolations: Option<Vec<InterpolatedValue>>,
-}
-
-impl syn::parse::Parse for ParsedInput {
-    fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
-        let context = input.parse()?;
-        input.parse::<token::Comma>()?;
-        let mut keys = Vec::new();
-        keys.push(input.parse()?);
-        while input.peek(token::Dot) {
-            input.parse::<token::Dot>()?;
-            keys.push(input.parse()?);
+/// Expands a message macro.
+///
+/// With `ambient`, the input has no locale argument and the message is looked
+/// up in `Locale::current()`, which `load_locales!` generates when the
+/// `ambient-locale` feature is enabled.
+pub fn t_macro(
+    tokens: TokenStream,
+    output_type: OutputType,
+    ambient: bool,
+) -> Result<TokenStream, Error> {
+    let parse_input = |tokens| {
+        if ambient {
+            ParsedInput::parse_ambient.parse2(tokens)
+        } else {
+            syn::parse2(tokens)
         }
-        let interpolations = match input.parse::<token::Comma>() {
-            Ok(_) => {
-                let interpolations = input
-                    .parse_terminated(InterpolatedValue::parse, token::Comma)?
-                    .into_iter()
-                    .collect();
-                Some(interpolations)
-            }
-            Err(_) if input.is_empty() => None,
-            Err(err) => return Err(err),
-        };
-        Ok(ParsedInput {
-            context,
-            keys,
-            interpolations,
-        })
-    }
+    };
+    Ok(match output_type {
+        OutputType::String => string_expr(parse_input(tokens)?),
+        OutputType::Display => display_expr(parse_input(tokens)?),
+        OutputType::Write => {
+            let WriteInput { writer, input } =
+                (|input: syn::parse::ParseStream| WriteInput::parse(input, ambient))
+                    .parse2(tokens)?;
+            let display = display_expr(input);
+            quote!(::std::write!(#writer, "{}", #display))
+        }
+    })
 }
 
-pub enum InterpolatedValue {
-    Var(Ident),
-    AssignedVar { key: Ident, value: Expr },
-}
-impl syn::parse::Parse for InterpolatedValue {
-    fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
-        let key = input.parse()?;
-        let value = if input.peek(syn::Token![=]) {
-            input.parse::<syn::Token![=]>()?;
-            let value = input.parse()?;
-            InterpolatedValue::AssignedVar { key, value }
-        } else {
-            InterpolatedValue::Var(key)
-        };
-        Ok(value)
-    }
-}
-pub fn t_macro(tokens: TokenStream, output_type: OutputType) -> Result<TokenStream, Error> {
+fn string_expr(input: ParsedInput) -> TokenStream {
     let ParsedInput {
         context,
         keys,
         interpolations,
-    } = syn::parse2(tokens)?;
+    } = input;
 
     let mut get_key = quote!(#context.get_strings().);
     get_key.append_separated(keys, quote!(.));
-    let build_fn = match output_type {
-        OutputType::String => quote!(build_string),
-    };
-    let inner = if let Some(interpolations) = interpolations {
+    if let Some(interpolations) = interpolations {
         let (keys, values): (Vec<_>, Vec<_>) = interpolations
             .iter()
             .map(|iv| match iv {
-                InterpolatedValue::Var(ident) => (ident.clone(), quote!(#ident)),
-                InterpolatedValue::AssignedVar { key, value } => (key.clone(), quote!(#value)),
+                InterpolatedValue::Var(ident) => {
+                    let var = mangle(&ident.to_string());
+                    (var.clone(), quote!(#var))
+                }
+                InterpolatedValue::AssignedVar { key, value } => {
+                    (mangle(&key.to_string()), quote!(#value))
+                }
             })
             .unzip();
         let params = quote! {
@@ -105,6 +95,7 @@ pub fn t_macro(tokens: TokenStream, output_type: OutputType) -> Result<TokenStre
                 InterpolatedValue::Var(key) | InterpolatedValue::AssignedVar { key, .. } => key,
             };
             let builder = Ident::new(&format!("arg_{}", key), Span::call_site());
+            let key = mangle(&key.to_string());
             quote!(#builder(&#key))
         });
         quote! {
@@ -117,7 +108,7 @@ pub fn t_macro(tokens: TokenStream, output_type: OutputType) -> Result<TokenStre
                     let _key = _key.#builders;
                 )*
                 #[deny(deprecated)]
-                _key.#build_fn()
+                _key.build_string()
             }
         }
     } else {
@@ -126,9 +117,35 @@ pub fn t_macro(tokens: TokenStream, output_type: OutputType) -> Result<TokenStre
                 #[allow(unused)]
                 use mf1::BuildStr;
                 let _key = #get_key;
-                _key.#build_fn()
+                _key.build_string()
             }
         }
-    };
-    Ok(inner)
+    }
+}
+
+/// Builds a message as a single expression, so that the returned `impl Display`
+/// can borrow the arguments from the caller.
+///
+/// Arguments given as `name = value` are borrowed as temporaries, so they live
+/// until the end of the statement using the macro.
+fn display_expr(input: ParsedInput) -> TokenStream {
+    let ParsedInput {
+        context,
+        keys,
+        interpolations,
+    } = input;
+    let mut key = quote!(#context.get_strings().);
+    key.append_separated(keys, quote!(.));
+    for inter in interpolations.iter().flatten() {
+        let (name, value) = match inter {
+            InterpolatedValue::Var(name) => {
+                let var = mangle(&name.to_string());
+                (name, quote!(#var))
+            }
+            InterpolatedValue::AssignedVar { key, value } => (key, quote!(#value)),
+        };
+        let builder = Ident::new(&format!("arg_{}", name), Span::call_site());
+        key = quote!(#key.#builder(&(#value)));
+    }
+    quote!(mf1::BuildStr::build_display(#key))
 }
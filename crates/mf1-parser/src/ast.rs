@@ -96,7 +96,7 @@ where
     pub tokens: Cow<'b, [Token<'a, 'b, StrMaybeOwned>]>,
 }
 
-#[derive(Debug, Copy, Clone, Hash, PartialEq, Eq)]
+#[derive(Debug, Copy, Clone, Hash, PartialEq, Eq, PartialOrd, Ord)]
 pub enum ArgType {
     OrdinalArg,
     PlainArg,
@@ -104,6 +104,22 @@ pub enum ArgType {
     FunctionArg,
 }
 
+impl ArgType {
+    /// Returns the most specific type compatible with both uses of an
+    /// argument, or `None` if they conflict.
+    ///
+    /// Plain arguments are only displayed, so they are compatible with every
+    /// other use: a number can be shown as plain text and also drive a plural,
+    /// and a string can be shown as plain text and also drive a select.
+    pub fn unify(self, other: ArgType) -> Option<ArgType> {
+        match (self, other) {
+            (a, b) if a == b => Some(a),
+            (ArgType::PlainArg, other) | (other, ArgType::PlainArg) => Some(other),
+            _ => None,
+        }
+    }
+}
+
 pub trait TokenSlice<'a, T> {
     fn get_args(&'a self) -> HashMap<&'a T, HashSet<ArgType>>
     where
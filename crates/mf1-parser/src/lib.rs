@@ -1,5 +1,8 @@
 mod ast;
 mod parser;
+mod printer;
 
-pub use ast::{ArgType, Token, TokenSlice};
+pub use ast::{ArgType, PluralCase, SelectCase, Token, TokenSlice};
+pub use icu_plurals::PluralCategory;
 pub use parser::{parse, LexerSpan};
+pub use printer::{plural_category_name, to_source};
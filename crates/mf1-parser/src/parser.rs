@@ -3,7 +3,7 @@ use logos::{Lexer, Logos};
 use std::borrow::Cow;
 use std::ops::Deref;
 
-use crate::ast::{SelectCase, Token as AstToken};
+use crate::ast::{PluralCase, SelectCase, Token as AstToken};
 
 #[cfg(test)]
 mod test;
@@ -214,7 +214,13 @@ where
                 }
             }
 
-            ArgToken::Ident => todo!(),
+            ArgToken::Ident => (
+                Err((
+                    "Function arguments are not supported".to_owned(),
+                    lex.span(),
+                )),
+                lex,
+            ),
 
             ArgToken::End => {
                 // Just a simple arg, but with an end comma
@@ -358,24 +364,26 @@ where
                 if !expect_colon {
                     return (
                         match parent_type {
-                            ArgToken::Plural => {
-                                let _token: std::result::Result<AstToken<T>, ()> =
-                                    Ok(AstToken::Plural {
-                                        arg: arg.into(),
-                                        cases: Cow::Owned(vec![]),
-                                        plural_offset: offset.0,
-                                    });
-                                todo!()
-                            }
-                            ArgToken::SelectOrdinal => {
-                                let _token: std::result::Result<AstToken<T>, ()> =
-                                    Ok(AstToken::SelectOrdinal {
-                                        arg: arg.into(),
-                                        cases: Cow::Owned(vec![]),
-                                        plural_offset: offset.0,
-                                    });
-                                todo!()
-                            }
+                            ArgToken::Plural => match plural_cases(cases) {
+                                Ok(cases) => Ok(AstToken::Plural {
+                                    arg: arg.into(),
+                                    cases: Cow::Owned(cases),
+                                    plural_offset: offset.0,
+                                }),
+                                Err(key) => {
+                                    Err((format!("Invalid plural category: {}", &*key), lex.span()))
+                                }
+                            },
+                            ArgToken::SelectOrdinal => match plural_cases(cases) {
+                                Ok(cases) => Ok(AstToken::SelectOrdinal {
+                                    arg: arg.into(),
+                                    cases: Cow::Owned(cases),
+                                    plural_offset: offset.0,
+                                }),
+                                Err(key) => {
+                                    Err((format!("Invalid plural category: {}", &*key), lex.span()))
+                                }
+                            },
                             ArgToken::Select => Ok(AstToken::Select {
                                 arg: arg.into(),
                                 cases: Cow::Owned(cases),
@@ -394,7 +402,30 @@ where
             }
         }
     }
-    todo! {}
+    (
+        Err((
+            "Message unexpectedly ended within select".to_owned(),
+            lex.span(),
+        )),
+        lex,
+    )
+}
+
+/// Converts the cases of a `plural` or `selectordinal` argument into plural
+/// cases, returning the first key that is not a CLDR plural category.
+fn plural_cases<'source, 'a, T>(
+    cases: Vec<SelectCase<'source, 'a, T>>,
+) -> std::result::Result<Vec<PluralCase<'source, 'a, T>>, T>
+where
+    T: Deref<Target = str> + Clone,
+{
+    cases
+        .into_iter()
+        .map(|case| {
+            let key = case.key.clone();
+            PluralCase::try_from(case).map_err(|_| key)
+        })
+        .collect()
 }
 
 // enum Modes<'source> {
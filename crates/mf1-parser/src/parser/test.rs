@@ -1,5 +1,7 @@
-use crate::ast::{SelectCase, Token};
+use crate::ast::{PluralCase, SelectCase, Token};
 use crate::parser::parse;
+use crate::printer::to_source;
+use icu_plurals::PluralCategory;
 
 pub(crate) fn parse_ui(src: &str) -> Vec<Token<'_, '_, &str>> {
     match parse(src) {
@@ -209,3 +211,83 @@ fn select_whitespace_agnostic() {
         res.clone()
     );
 }
+
+#[test]
+fn plural_cases() {
+    let res = Token::Plural {
+        arg: "count",
+        plural_offset: None,
+        cases: vec![
+            PluralCase {
+                key: PluralCategory::One,
+                tokens: vec![Token::Octothorpe {}, Token::Content { value: " item" }].into(),
+            },
+            PluralCase {
+                key: PluralCategory::Other,
+                tokens: vec![Token::Octothorpe {}, Token::Content { value: " items" }].into(),
+            },
+        ]
+        .into(),
+    };
+    parse_assert!("{count, plural, one {# item} other {# items}}", res.clone());
+    parse_assert!("{count,plural,one{# item}other{# items}}", res.clone());
+}
+
+#[test]
+fn plural_offset() {
+    parse_assert!(
+        "{n, selectordinal, offset:1 one {#st} other {#th}}",
+        Token::SelectOrdinal {
+            arg: "n",
+            plural_offset: Some(1),
+            cases: vec![
+                PluralCase {
+                    key: PluralCategory::One,
+                    tokens: vec![Token::Octothorpe {}, Token::Content { value: "st" }].into(),
+                },
+                PluralCase {
+                    key: PluralCategory::Other,
+                    tokens: vec![Token::Octothorpe {}, Token::Content { value: "th" }].into(),
+                },
+            ]
+            .into(),
+        }
+    );
+}
+
+#[test]
+fn plural_invalid_category() {
+    assert!(parse::<&str>("{count, plural, single {a} other {b}}").is_err());
+}
+
+#[test]
+fn select_unterminated() {
+    assert!(parse::<&str>("{gender, select, male {he}").is_err());
+}
+
+#[test]
+fn function_arg_unsupported() {
+    assert_eq!(
+        parse::<&str>("{n, number} items"),
+        Err(("Function arguments are not supported".to_owned(), 4..10))
+    );
+}
+
+macro_rules! roundtrip_assert {
+    ( $src:literal, $res:literal ) => {{
+        let tokens = parse_ui($src);
+        let source = to_source(&tokens);
+        assert_eq!(&source, $res);
+        assert_eq!(to_source(&parse_ui(&source)), source);
+    }};
+}
+
+#[test]
+fn print_roundtrip() {
+    roundtrip_assert!("Hello {name}!", "Hello {name}!");
+    roundtrip_assert!("I don''t '{know}'", "I don''t '{'know'}'");
+    roundtrip_assert!(
+        "{ gender, select, male {He} other {They} } liked {n,plural,offset:1 one{# thing}other{# things}}",
+        "{gender, select, male {He} other {They}} liked {n, plural, offset:1 one {# thing} other {# things}}"
+    );
+}
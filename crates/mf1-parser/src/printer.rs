@@ -0,0 +1,116 @@
+use icu_plurals::PluralCategory;
+use std::fmt::Write;
+use std::ops::Deref;
+
+use crate::ast::{PluralCase, SelectCase, Token};
+
+/// Returns the CLDR name of a plural category, as used for the keys of
+/// `plural` and `selectordinal` cases.
+pub fn plural_category_name(category: PluralCategory) -> &'static str {
+    match category {
+        PluralCategory::Zero => "zero",
+        PluralCategory::One => "one",
+        PluralCategory::Two => "two",
+        PluralCategory::Few => "few",
+        PluralCategory::Many => "many",
+        PluralCategory::Other => "other",
+    }
+}
+
+/// Serialises a parsed message back into MessageFormat 1 source.
+///
+/// Content is escaped so that parsing the output yields the same tokens.
+pub fn to_source<T>(tokens: &[Token<'_, '_, T>]) -> String
+where
+    T: Deref<Target = str> + Clone,
+{
+    let mut out = String::new();
+    write_tokens(&mut out, tokens);
+    out
+}
+
+fn write_tokens<T>(out: &mut String, tokens: &[Token<'_, '_, T>])
+where
+    T: Deref<Target = str> + Clone,
+{
+    for token in tokens {
+        match token {
+            Token::Content { value } => write_content(out, value),
+            Token::PlainArg { arg } => {
+                let _ = write!(out, "{{{}}}", &**arg);
+            }
+            Token::FunctionArg { arg, key, param } => {
+                let _ = write!(out, "{{{}, {}", &**arg, &**key);
+                if let Some(param) = param {
+                    out.push_str(", ");
+                    write_tokens(out, param);
+                }
+                out.push('}');
+            }
+            Token::Plural {
+                arg,
+                cases,
+                plural_offset,
+            } => write_plural(out, arg, "plural", cases, *plural_offset),
+            Token::SelectOrdinal {
+                arg,
+                cases,
+                plural_offset,
+            } => write_plural(out, arg, "selectordinal", cases, *plural_offset),
+            Token::Select {
+                arg,
+                cases,
+                plural_offset: _,
+            } => write_select(out, arg, cases),
+            Token::Octothorpe {} => out.push('#'),
+        }
+    }
+}
+
+fn write_content(out: &mut String, value: &str) {
+    for c in value.chars() {
+        match c {
+            '\'' => out.push_str("''"),
+            '{' | '}' | '#' => {
+                out.push('\'');
+                out.push(c);
+                out.push('\'');
+            }
+            c => out.push(c),
+        }
+    }
+}
+
+fn write_plural<T>(
+    out: &mut String,
+    arg: &str,
+    kind: &str,
+    cases: &[PluralCase<'_, '_, T>],
+    plural_offset: Option<i32>,
+) where
+    T: Deref<Target = str> + Clone,
+{
+    let _ = write!(out, "{{{}, {},", arg, kind);
+    if let Some(offset) = plural_offset {
+        let _ = write!(out, " offset:{}", offset);
+    }
+    for case in cases {
+        let _ = write!(out, " {} {{", plural_category_name(case.key));
+        write_tokens(out, &case.tokens);
+        out.push('}');
+    }
+    out.push('}');
+}
+
+fn write_select<T>(out: &mut String, arg: &str, cases: &[SelectCase<'_, '_, T>])
+where
+    T: Deref<Target = str> + Clone,
+{
+    let _ = write!(out, "{{{}, select,", arg);
+    for case in cases {
+        let _ = write!(out, " {} {{", &*case.key);
+        write_tokens(out, &case.tokens);
+        out.push('}');
+    }
+    out.push('}');
+}
@@ -0,0 +1,101 @@
+//! The tables of messages generated by `load_locales!` with
+//! `codegen = "compact"`.
+//!
+//! Each locale has a single string with the text of all its messages, and
+//! each message is an index into the spans of that string. Messages are
+//! formatted by code shared between every locale where the message has the
+//! same shape, which is chosen by the `shape` of its entry.
+
+/// The start and end of a piece of text, in bytes.
+pub type Span = (u32, u32);
+
+/// A message of a locale: the shape of its formatter, and its first span.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Entry {
+    pub shape: u16,
+    pub first_span: u32,
+}
+
+impl Entry {
+    /// A message the locale doesn't translate, which is taken from its fallback.
+    pub const MISSING: Entry = Entry {
+        shape: u16::MAX,
+        first_span: 0,
+    };
+}
+
+/// The messages of a locale, by key.
+#[derive(Debug, Copy, Clone)]
+pub struct LocaleTable {
+    pub text: &'static str,
+    pub spans: &'static [Span],
+    pub messages: &'static [Entry],
+}
+
+impl LocaleTable {
+    /// The table of a locale which is not compiled in, which takes every
+    /// message from its fallback.
+    pub const EMPTY: LocaleTable = LocaleTable {
+        text: "",
+        spans: &[],
+        messages: &[],
+    };
+}
+
+/// The messages of every locale.
+#[derive(Debug, Copy, Clone)]
+pub struct Catalog {
+    /// The tag of each locale, by index.
+    pub tags: &'static [&'static str],
+    /// The index of the locale each locale takes missing messages from. The
+    /// base locale is its own fallback.
+    pub fallbacks: &'static [u16],
+    pub locales: &'static [LocaleTable],
+}
+
+impl Catalog {
+    /// Finds a message in a locale or its fallbacks, returning the index of
+    /// the locale it was found in, its shape, and its text.
+    #[inline]
+    pub fn message(&self, locale: u16, key: u32) -> (usize, u16, Texts) {
+        let mut locale = usize::from(locale);
+        loop {
+            let table = &self.locales[locale];
+            match table.messages.get(key as usize) {
+                Some(entry) if *entry != Entry::MISSING => {
+                    let texts = Texts {
+                        text: table.text,
+                        spans: &table.spans[entry.first_span as usize..],
+                    };
+                    return (locale, entry.shape, texts);
+                }
+                _ => {
+                    let fallback = usize::from(self.fallbacks[locale]);
+                    assert_ne!(fallback, locale, "the base locale has every message");
+                    locale = fallback;
+                }
+            }
+        }
+    }
+
+    /// Finds a message without arguments.
+    #[inline]
+    pub fn string(&self, locale: u16, key: u32) -> &'static str {
+        self.message(locale, key).2.get(0)
+    }
+}
+
+/// The pieces of text of a message, in the order its formatter writes them.
+#[derive(Debug, Copy, Clone)]
+pub struct Texts {
+    text: &'static str,
+    spans: &'static [Span],
+}
+
+impl Texts {
+    #[inline]
+    pub fn get(&self, i: usize) -> &'static str {
+        let (start, end) = self.spans[i];
+        &self.text[start as usize..end as usize]
+    }
+}
@@ -0,0 +1,261 @@
+//! Messages looked up by path at runtime, with arguments given in a map.
+
+use std::{
+    borrow::Borrow,
+    collections::{BTreeMap, HashMap},
+    fmt::{self, Display},
+    hash::{BuildHasher, Hash},
+};
+
+use crate::{Formattable, Number, Select};
+
+/// An argument of a message looked up at runtime.
+#[derive(Clone, Copy)]
+pub enum Arg<'a> {
+    /// Text, for plain and `select` arguments.
+    Str(&'a str),
+    /// A number, for plain, `plural` and `selectordinal` arguments.
+    Number(&'a dyn Number),
+    /// Any other value, for plain arguments.
+    Display(&'a dyn Display),
+}
+
+impl<'a> Arg<'a> {
+    /// The key of a value passed as a `select` argument.
+    pub fn select<T: Select + ?Sized>(value: &T) -> Self {
+        Arg::Str(value.select_key())
+    }
+}
+
+impl fmt::Debug for Arg<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Arg::Str(value) => f.debug_tuple("Str").field(value).finish(),
+            Arg::Number(value) => f
+                .debug_tuple("Number")
+                .field(&format_args!("{value}"))
+                .finish(),
+            Arg::Display(value) => f
+                .debug_tuple("Display")
+                .field(&format_args!("{value}"))
+                .finish(),
+        }
+    }
+}
+
+impl<'a> From<&'a str> for Arg<'a> {
+    fn from(value: &'a str) -> Self {
+        Arg::Str(value)
+    }
+}
+
+impl<'a> From<&'a String> for Arg<'a> {
+    fn from(value: &'a String) -> Self {
+        Arg::Str(value)
+    }
+}
+
+impl<'a, T: Number> From<&'a T> for Arg<'a> {
+    fn from(value: &'a T) -> Self {
+        Arg::Number(value)
+    }
+}
+
+/// The arguments of a message looked up at runtime, by name.
+///
+/// This is implemented for `HashMap`s and `BTreeMap`s from names to [`Arg`]s,
+/// for slices and arrays of `(name, arg)` pairs, and for `()` as no arguments.
+pub trait Args {
+    fn get(&self, name: &str) -> Option<&Arg<'_>>;
+}
+
+impl Args for () {
+    fn get(&self, _: &str) -> Option<&Arg<'_>> {
+        None
+    }
+}
+
+impl<K, S> Args for HashMap<K, Arg<'_>, S>
+where
+    K: Borrow<str> + Hash + Eq,
+    S: BuildHasher,
+{
+    fn get(&self, name: &str) -> Option<&Arg<'_>> {
+        HashMap::get(self, name)
+    }
+}
+
+impl<K: Borrow<str> + Ord> Args for BTreeMap<K, Arg<'_>> {
+    fn get(&self, name: &str) -> Option<&Arg<'_>> {
+        BTreeMap::get(self, name)
+    }
+}
+
+impl<K: Borrow<str>> Args for [(K, Arg<'_>)] {
+    fn get(&self, name: &str) -> Option<&Arg<'_>> {
+        self.iter()
+            .find(|(key, _)| key.borrow() == name)
+            .map(|(_, arg)| arg)
+    }
+}
+
+impl<K: Borrow<str>, const N: usize> Args for [(K, Arg<'_>); N] {
+    fn get(&self, name: &str) -> Option<&Arg<'_>> {
+        Args::get(self.as_slice(), name)
+    }
+}
+
+/// An error formatting a message looked up at runtime.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FormatError {
+    /// An argument of the message was not given.
+    MissingArg(&'static str),
+    /// An argument was given with a type the message can't use.
+    WrongArgType {
+        arg: &'static str,
+        expected: &'static str,
+    },
+    /// A `select` argument with an enum type was given a key that is not one
+    /// of its values.
+    UnknownSelectKey { arg: &'static str },
+    /// The sink returned an error.
+    Fmt,
+}
+
+impl Display for FormatError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FormatError::MissingArg(arg) => write!(f, "Missing argument {arg:?}"),
+            FormatError::WrongArgType { arg, expected } => {
+                write!(f, "Argument {arg:?} must be {expected}")
+            }
+            FormatError::UnknownSelectKey { arg } => {
+                write!(f, "Argument {arg:?} is not a key of its select type")
+            }
+            FormatError::Fmt => f.write_str("Formatter error"),
+        }
+    }
+}
+
+impl std::error::Error for FormatError {}
+
+impl From<fmt::Error> for FormatError {
+    fn from(_: fmt::Error) -> Self {
+        FormatError::Fmt
+    }
+}
+
+/// A message that can be formatted with arguments given at runtime.
+///
+/// This is implemented by the generated messages, and is only used through
+/// [`MessageRef`].
+#[doc(hidden)]
+pub trait DynamicMessage {
+    fn arg_names(&self) -> &'static [&'static str];
+
+    fn write_dynamic(&self, out: &mut dyn Formattable, args: &dyn Args) -> Result<(), FormatError>;
+}
+
+impl DynamicMessage for &'static str {
+    fn arg_names(&self) -> &'static [&'static str] {
+        &[]
+    }
+
+    fn write_dynamic(&self, out: &mut dyn Formattable, _: &dyn Args) -> Result<(), FormatError> {
+        Ok(out.write_str(self)?)
+    }
+}
+
+/// A message looked up by its path, such as with `Mf1Keys::get`.
+///
+/// Unlike the fields of `Mf1Keys`, its arguments are only checked when it is
+/// formatted.
+#[derive(Clone, Copy)]
+pub struct MessageRef<'a> {
+    message: &'a dyn DynamicMessage,
+}
+
+impl<'a> MessageRef<'a> {
+    #[doc(hidden)]
+    pub fn new(message: &'a dyn DynamicMessage) -> Self {
+        MessageRef { message }
+    }
+
+    /// The names of the arguments of the message, in sorted order.
+    pub fn arg_names(&self) -> &'static [&'static str] {
+        self.message.arg_names()
+    }
+
+    /// Writes the message into a sink.
+    pub fn write_to(&self, out: &mut dyn Formattable, args: &dyn Args) -> Result<(), FormatError> {
+        self.message.write_dynamic(out, args)
+    }
+
+    /// Formats the message as a string.
+    pub fn format(&self, args: &dyn Args) -> Result<String, FormatError> {
+        let mut out = String::new();
+        self.write_to(&mut out, args)?;
+        Ok(out)
+    }
+}
+
+impl fmt::Debug for MessageRef<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MessageRef")
+            .field("arg_names", &self.arg_names())
+            .finish_non_exhaustive()
+    }
+}
+
+fn get_arg<'a>(args: &'a dyn Args, name: &'static str) -> Result<&'a Arg<'a>, FormatError> {
+    args.get(name).ok_or(FormatError::MissingArg(name))
+}
+
+/// Gets a plain argument, which can have any type.
+#[doc(hidden)]
+pub fn display_arg<'a>(
+    args: &'a dyn Args,
+    name: &'static str,
+) -> Result<&'a dyn Display, FormatError> {
+    Ok(match get_arg(args, name)? {
+        Arg::Str(value) => value,
+        Arg::Number(value) => *value,
+        Arg::Display(value) => *value,
+    })
+}
+
+/// Gets a `plural` or `selectordinal` argument.
+#[doc(hidden)]
+pub fn number_arg<'a>(
+    args: &'a dyn Args,
+    name: &'static str,
+) -> Result<&'a dyn Number, FormatError> {
+    match get_arg(args, name)? {
+        Arg::Number(value) => Ok(*value),
+        _ => Err(FormatError::WrongArgType {
+            arg: name,
+            expected: "a number",
+        }),
+    }
+}
+
+/// Gets a `select` argument.
+#[doc(hidden)]
+pub fn str_arg<'a>(args: &'a dyn Args, name: &'static str) -> Result<&'a str, FormatError> {
+    match get_arg(args, name)? {
+        Arg::Str(value) => Ok(*value),
+        _ => Err(FormatError::WrongArgType {
+            arg: name,
+            expected: "a string",
+        }),
+    }
+}
+
+/// Gets a `select` argument with an enum type, from its key.
+#[doc(hidden)]
+pub fn select_arg<T: Select>(
+    args: &dyn Args,
+    name: &'static str,
+) -> Result<&'static T, FormatError> {
+    T::from_select_key(str_arg(args, name)?).ok_or(FormatError::UnknownSelectKey { arg: name })
+}
@@ -1,7 +1,38 @@
-use std::{borrow::Cow, error::Error};
+use std::{borrow::Cow, fmt, io};
 
 #[cfg(feature = "macros")]
-pub use mf1_macros::{load_locales, t_l_string};
+pub use mf1_macros::{load_locales, t_l_display, t_l_string, t_l_write, Mf1Select};
+#[cfg(feature = "ambient-locale")]
+pub use mf1_macros::{t_display, t_string, t_write};
+
+#[doc(hidden)]
+pub mod compact;
+mod dynamic;
+mod locale;
+mod number;
+mod reflect;
+mod select;
+
+#[doc(hidden)]
+pub use dynamic::{display_arg, number_arg, select_arg, str_arg, DynamicMessage};
+pub use dynamic::{Arg, Args, FormatError, MessageRef};
+pub use fixed_decimal::FixedDecimal;
+pub use icu_locid;
+#[doc(hidden)]
+pub use icu_plurals;
+#[doc(hidden)]
+pub use locale::locale_tag_eq;
+pub use locale::{ParseLocaleError, TextDirection};
+pub use number::Number;
+#[doc(hidden)]
+pub use number::{plural_rules, plural_value};
+pub use reflect::{ArgInfo, ArgType, KeyInfo};
+pub use select::Select;
+#[doc(hidden)]
+pub use select::{select_covers, select_has_key};
+#[cfg(feature = "serde")]
+#[doc(hidden)]
+pub use serde;
 
 /// This is used to call `.build` on `&str` when building interpolations.
 ///
@@ -32,21 +63,101 @@ impl BuildStr for &'static str {
     }
 }
 
-#[doc(hidden)]
-pub trait Formattable<'a> {
-    // type Error;
-    fn write_str(&mut self, data: &str) -> Result<(), Box<dyn Error>>;
-    fn write_fmt(&mut self, args: std::fmt::Arguments) -> Result<(), Box<dyn Error>>;
+/// A sink that messages can be written into.
+///
+/// This is implemented for every [`fmt::Write`], including `String` and
+/// `fmt::Formatter`. Use [`IoWriter`] to write into an [`io::Write`].
+///
+/// Like [`fmt::Write`], errors carry no information. Sinks with their own
+/// errors should store them, as [`IoWriter`] does.
+pub trait Formattable {
+    fn write_str(&mut self, data: &str) -> fmt::Result;
+    fn write_fmt(&mut self, args: fmt::Arguments) -> fmt::Result;
+}
+
+impl<W: fmt::Write + ?Sized> Formattable for W {
+    #[inline]
+    fn write_str(&mut self, data: &str) -> fmt::Result {
+        fmt::Write::write_str(self, data)
+    }
+
+    #[inline]
+    fn write_fmt(&mut self, args: fmt::Arguments) -> fmt::Result {
+        fmt::Write::write_fmt(self, args)
+    }
+}
+
+/// Adapts an [`io::Write`] into a [`Formattable`], so messages can be
+/// streamed into files, sockets or response bodies.
+///
+/// When a write fails, the [`io::Error`] is kept so it can be returned from
+/// [`IoWriter::write_message`] or taken with [`IoWriter::take_error`].
+#[derive(Debug)]
+pub struct IoWriter<W> {
+    writer: W,
+    error: Option<io::Error>,
+}
+
+impl<W: io::Write> IoWriter<W> {
+    pub fn new(writer: W) -> Self {
+        IoWriter {
+            writer,
+            error: None,
+        }
+    }
+
+    pub fn get_ref(&self) -> &W {
+        &self.writer
+    }
+
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.writer
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+
+    /// Takes the error from the last failed write, if any.
+    pub fn take_error(&mut self) -> Option<io::Error> {
+        self.error.take()
+    }
+
+    /// Writes a message, returning the error from the writer if it fails.
+    pub fn write_message<M: Message + ?Sized>(&mut self, message: &M) -> io::Result<()> {
+        message.write_to(self).map_err(|fmt::Error| {
+            self.take_error()
+                .unwrap_or_else(|| io::Error::other("formatter error"))
+        })
+    }
 }
 
-impl<'a> Formattable<'a> for core::fmt::Formatter<'a> {
-    // type Error = std::fmt::Error;
+impl<W: io::Write> Formattable for IoWriter<W> {
+    fn write_str(&mut self, data: &str) -> fmt::Result {
+        self.writer.write_all(data.as_bytes()).map_err(|err| {
+            self.error = Some(err);
+            fmt::Error
+        })
+    }
 
-    fn write_str(&mut self, data: &str) -> Result<(), Box<dyn Error>> {
-        Ok(self.write_str(data)?)
+    fn write_fmt(&mut self, args: fmt::Arguments) -> fmt::Result {
+        self.writer.write_fmt(args).map_err(|err| {
+            self.error = Some(err);
+            fmt::Error
+        })
     }
+}
+
+/// A message that can be written into any [`Formattable`] sink, without
+/// going through [`fmt::Display`].
+///
+/// This is implemented for messages returned by `t_l_display!`.
+pub trait Message {
+    fn write_to(&self, out: &mut dyn Formattable) -> fmt::Result;
+}
 
-    fn write_fmt(&mut self, args: std::fmt::Arguments) -> Result<(), Box<dyn Error>> {
-        Ok(self.write_fmt(args)?)
+impl Message for str {
+    fn write_to(&self, out: &mut dyn Formattable) -> fmt::Result {
+        out.write_str(self)
     }
 }
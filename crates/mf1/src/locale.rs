@@ -0,0 +1,71 @@
+//! Types used by the generated `Locale` enum.
+
+use std::fmt;
+
+/// The direction text is written in, as returned by `Locale::text_direction`.
+#[derive(Debug, Copy, Clone, Hash, PartialEq, Eq)]
+pub enum TextDirection {
+    LeftToRight,
+    RightToLeft,
+}
+
+impl TextDirection {
+    /// The value of the HTML `dir` attribute for this direction.
+    pub fn as_html_dir(self) -> &'static str {
+        match self {
+            TextDirection::LeftToRight => "ltr",
+            TextDirection::RightToLeft => "rtl",
+        }
+    }
+}
+
+/// Whether `s` is the locale `tag`, ignoring ASCII case and treating `_` as
+/// `-`, so `pt_br` is `pt-BR`.
+#[doc(hidden)]
+pub fn locale_tag_eq(s: &str, tag: &str) -> bool {
+    let normalize = |b: u8| match b {
+        b'_' => b'-',
+        b => b.to_ascii_lowercase(),
+    };
+    s.bytes().map(normalize).eq(tag.bytes().map(normalize))
+}
+
+/// The error returned when parsing a locale that is not in the catalog.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseLocaleError {
+    locale: String,
+    expected: &'static [&'static str],
+}
+
+impl ParseLocaleError {
+    #[doc(hidden)]
+    pub fn new(locale: &str, expected: &'static [&'static str]) -> Self {
+        ParseLocaleError {
+            locale: locale.to_owned(),
+            expected,
+        }
+    }
+
+    /// The string that was parsed.
+    pub fn locale(&self) -> &str {
+        &self.locale
+    }
+
+    /// The locales of the catalog.
+    pub fn expected(&self) -> &'static [&'static str] {
+        self.expected
+    }
+}
+
+impl fmt::Display for ParseLocaleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Unknown locale {:?}, expected one of: {}",
+            self.locale,
+            self.expected.join(", ")
+        )
+    }
+}
+
+impl std::error::Error for ParseLocaleError {}
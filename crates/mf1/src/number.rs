@@ -0,0 +1,285 @@
+use std::fmt::{self, Display, Write};
+
+use fixed_decimal::{FixedDecimal, Sign};
+use icu_locid::Locale;
+use icu_plurals::{PluralRuleType, PluralRules};
+
+/// A number that can be passed as a `plural` or `selectordinal` argument.
+///
+/// This is implemented for all integer and float types, and for
+/// [`FixedDecimal`], so the correct plural category can be chosen for
+/// numbers like `1.5` or `u64::MAX`.
+pub trait Number: Display {
+    /// Returns the number as a decimal, which gives the ICU plural operands.
+    fn to_decimal(&self) -> FixedDecimal;
+}
+
+macro_rules! impl_number_for_integer {
+    ($($ty:ty),*) => {
+        $(
+            impl Number for $ty {
+                fn to_decimal(&self) -> FixedDecimal {
+                    FixedDecimal::from(*self)
+                }
+            }
+        )*
+    };
+}
+
+impl_number_for_integer!(i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize);
+
+macro_rules! impl_number_for_float {
+    ($($ty:ty),*) => {
+        $(
+            impl Number for $ty {
+                /// Uses the shortest representation that round trips, so
+                /// `1.5f32` has one fraction digit. NaN and infinities are
+                /// treated as zero.
+                fn to_decimal(&self) -> FixedDecimal {
+                    if !self.is_finite() {
+                        return FixedDecimal::default();
+                    }
+                    float_decimal(format_args!("{:e}", self))
+                }
+            }
+        )*
+    };
+}
+
+impl_number_for_float!(f32, f64);
+
+/// Enough for the `{:e}` of any `f64`, such as `-2.2250738585072014e-308`.
+struct ExpBuffer {
+    bytes: [u8; 32],
+    len: usize,
+}
+
+impl Write for ExpBuffer {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        let end = self.len + s.len();
+        let bytes = self.bytes.get_mut(self.len..end).ok_or(fmt::Error)?;
+        bytes.copy_from_slice(s.as_bytes());
+        self.len = end;
+        Ok(())
+    }
+}
+
+/// Builds a decimal from a finite float written as `{:e}`, which has the
+/// digits of its shortest representation that round trips, without
+/// formatting it into a `String`.
+fn float_decimal(exp: fmt::Arguments) -> FixedDecimal {
+    let mut buffer = ExpBuffer {
+        bytes: [0; 32],
+        len: 0,
+    };
+    buffer.write_fmt(exp).expect("a float fits in the buffer");
+    let src = std::str::from_utf8(&buffer.bytes[..buffer.len]).expect("floats are ASCII");
+    let (mantissa, exponent) = src.split_once('e').expect("written with {:e}");
+    let (negative, mantissa) = match mantissa.strip_prefix('-') {
+        Some(mantissa) => (true, mantissa),
+        None => (false, mantissa),
+    };
+    let (int, fraction) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+    // At most 17 digits, so they fit in a `u64`.
+    let digits = int
+        .bytes()
+        .chain(fraction.bytes())
+        .fold(0u64, |n, digit| n * 10 + u64::from(digit - b'0'));
+    let exponent: i16 = exponent.parse().expect("written with {:e}");
+    let mut decimal = FixedDecimal::from(digits);
+    decimal.multiply_pow10(exponent - fraction.len() as i16);
+    if negative {
+        decimal.set_sign(Sign::Negative);
+    }
+    decimal
+}
+
+impl Number for FixedDecimal {
+    fn to_decimal(&self) -> FixedDecimal {
+        self.clone()
+    }
+}
+
+impl<T: Number + ?Sized> Number for &T {
+    fn to_decimal(&self) -> FixedDecimal {
+        (**self).to_decimal()
+    }
+}
+
+/// Returns `number - offset` as a decimal, for plurals with an `offset:`.
+/// The fraction digits of the number are kept, so `1.50` becomes `0.50`.
+#[doc(hidden)]
+pub fn plural_value(number: &dyn Number, offset: i32) -> FixedDecimal {
+    let decimal = number.to_decimal();
+    if offset == 0 {
+        return decimal;
+    }
+    // The number and the offset as integers scaled by the fraction digits.
+    let lower = *decimal.magnitude_range().start();
+    let scaled = decimal
+        .magnitude_range()
+        .rev()
+        .try_fold(0i128, |n, magnitude| {
+            n.checked_mul(10)?
+                .checked_add(i128::from(decimal.digit_at(magnitude)))
+        });
+    let scaled_offset = 10i128
+        .checked_pow(lower.unsigned_abs().into())
+        .and_then(|pow| pow.checked_mul(offset.into()));
+    let difference = match (scaled, scaled_offset) {
+        (Some(n), Some(offset)) if decimal.sign() == Sign::Negative => {
+            n.checked_neg().and_then(|n| n.checked_sub(offset))
+        }
+        (Some(n), Some(offset)) => n.checked_sub(offset),
+        _ => None,
+    };
+    match difference {
+        Some(difference) => {
+            let mut value = FixedDecimal::from(difference);
+            value.multiply_pow10(lower);
+            value
+        }
+        None => subtract_digits(&decimal, offset),
+    }
+}
+
+/// Returns `decimal - offset` for decimals with too many digits for an `i128`,
+/// working digit by digit.
+fn subtract_digits(decimal: &FixedDecimal, offset: i32) -> FixedDecimal {
+    let lower = *decimal.magnitude_range().start();
+    // Room for the ten digits of the offset, and a carry.
+    let upper = (*decimal.magnitude_range().end()).max(10) + 1;
+    let sign = if decimal.sign() == Sign::Negative {
+        -1
+    } else {
+        1
+    };
+    let mut digits: Vec<i64> = (lower..=upper)
+        .map(|magnitude| sign * i64::from(decimal.digit_at(magnitude)))
+        .collect();
+    let mut rest = i64::from(offset);
+    for digit in &mut digits[lower.unsigned_abs().into()..] {
+        *digit -= rest % 10;
+        rest /= 10;
+    }
+    // Each digit is now between -18 and 18. Carrying makes them all between
+    // 0 and 9, unless the difference is negative, which leaves a borrow.
+    let normalize = |digits: &mut [i64]| {
+        let mut carry = 0;
+        for digit in digits.iter_mut() {
+            let value = *digit + carry;
+            *digit = value.rem_euclid(10);
+            carry = value.div_euclid(10);
+        }
+        carry
+    };
+    let mut normalized = digits.clone();
+    let negative = normalize(&mut normalized) < 0;
+    if negative {
+        normalized = digits.iter().map(|digit| -digit).collect();
+        normalize(&mut normalized);
+    }
+    let mut src = String::with_capacity(normalized.len() + 2);
+    if negative {
+        src.push('-');
+    }
+    for (magnitude, digit) in (lower..=upper).zip(&normalized).rev() {
+        if magnitude == -1 {
+            src.push('.');
+        }
+        if *digit != 0 || magnitude <= 0 || src.len() > usize::from(negative) {
+            src.push(char::from(b'0' + *digit as u8));
+        }
+    }
+    src.parse().expect("a valid decimal")
+}
+
+/// Loads the plural rules for a locale, falling back to the root locale if
+/// the identifier is invalid.
+#[doc(hidden)]
+pub fn plural_rules(locale: &str, ordinal: bool) -> PluralRules {
+    let locale = locale.parse::<Locale>().unwrap_or_default();
+    let rule_type = if ordinal {
+        PluralRuleType::Ordinal
+    } else {
+        PluralRuleType::Cardinal
+    };
+    PluralRules::try_new(&(&locale).into(), rule_type)
+        .expect("compiled data contains plural rules for every locale")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn float_decimals() {
+        for float in [
+            0.0,
+            -0.0,
+            1.0,
+            1.5,
+            -2.25,
+            0.1,
+            1234.5,
+            1e21,
+            1e-7,
+            f64::MAX,
+            f64::MIN_POSITIVE,
+            5e-324,
+        ] {
+            assert_eq!(
+                float.to_decimal(),
+                float.to_string().parse::<FixedDecimal>().unwrap(),
+                "{float}"
+            );
+        }
+        assert_eq!(1.1f32.to_decimal().to_string(), "1.1");
+        assert_eq!(f64::NAN.to_decimal(), FixedDecimal::default());
+        assert_eq!(f32::INFINITY.to_decimal(), FixedDecimal::default());
+    }
+
+    #[test]
+    fn offsets() {
+        let value = |number: &dyn Number, offset| plural_value(number, offset).to_string();
+        assert_eq!(value(&5, 0), "5");
+        assert_eq!(value(&5, 1), "4");
+        assert_eq!(value(&0, 1), "-1");
+        assert_eq!(value(&-3i8, 2), "-5");
+        assert_eq!(value(&1.5, 1), "0.5");
+        assert_eq!(value(&0.25, 1), "-0.75");
+        assert_eq!(value(&"1.50".parse::<FixedDecimal>().unwrap(), 1), "0.50");
+        assert_eq!(value(&u128::MAX, 1), (u128::MAX - 1).to_string());
+        assert_eq!(
+            value(&u128::MAX, -1),
+            "340282366920938463463374607431768211456"
+        );
+        assert_eq!(
+            value(&i128::MIN, 1),
+            "-170141183460469231731687303715884105729"
+        );
+        let tiny: FixedDecimal = format!("0.{}1", "0".repeat(40)).parse().unwrap();
+        assert_eq!(value(&tiny, 1), format!("-0.{}9", "9".repeat(40)));
+    }
+
+    #[test]
+    fn offsets_digit_by_digit() {
+        for (src, offset) in [
+            ("5", 1),
+            ("0", 1),
+            ("-3", 2),
+            ("1.50", 1),
+            ("0.25", 1),
+            ("-0.5", -1),
+            ("999", -1),
+            ("1000", 1),
+        ] {
+            let decimal: FixedDecimal = src.parse().unwrap();
+            assert_eq!(
+                subtract_digits(&decimal, offset),
+                plural_value(&decimal, offset),
+                "{src} - {offset}"
+            );
+        }
+    }
+}
@@ -0,0 +1,47 @@
+//! Metadata about the messages generated by `load_locales!`.
+
+/// The type of a message argument, which decides what can be passed for it.
+#[derive(Debug, Copy, Clone, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ArgType {
+    /// Used by a `plural` or `selectordinal`, and takes a [`Number`](crate::Number).
+    OrdinalArg,
+    /// Only displayed, and takes any `Display` value.
+    PlainArg,
+    /// Used by a `select`, and takes a `&str` or a [`Select`](crate::Select)
+    /// type declared in the metadata of the message.
+    SelectArg,
+    /// Formatted by a function, such as `{when, date}`.
+    FunctionArg,
+}
+
+/// An argument of a message.
+#[derive(Debug, Copy, Clone, Hash, PartialEq, Eq)]
+pub struct ArgInfo {
+    pub name: &'static str,
+    pub arg_type: ArgType,
+    /// The type declared for a `select` argument in the metadata of the
+    /// message, such as `crate::Pronoun`.
+    pub select_type: Option<&'static str>,
+}
+
+/// A message of the catalog, as listed in the generated `Locale::KEYS`.
+#[derive(Debug, Copy, Clone, Hash, PartialEq, Eq)]
+pub struct KeyInfo<L: 'static> {
+    /// The `.`-separated path of the message, such as `nested.keys`.
+    pub path: &'static str,
+    /// The arguments of the message, sorted by name.
+    pub args: &'static [ArgInfo],
+    /// The locales with their own translation of the message, including the
+    /// base locale.
+    pub translated: &'static [L],
+    /// The locales without their own translation, which take the message from
+    /// their fallback locale.
+    pub fallbacks: &'static [L],
+}
+
+impl<L: PartialEq> KeyInfo<L> {
+    /// Whether `locale` has its own translation of the message.
+    pub fn is_translated(&self, locale: L) -> bool {
+        self.translated.contains(&locale)
+    }
+}
@@ -0,0 +1,84 @@
+/// A type that can be passed as a `select` argument, usually an enum deriving
+/// [`Mf1Select`](crate::Mf1Select).
+///
+/// To use it for an argument, declare it in the metadata of the message:
+///
+/// ```json
+/// "@liked": { "args": { "gender": { "type": "crate::Gender" } } }
+/// ```
+///
+/// Every locale must then have a case for each key, or an `other` case, and
+/// cases that are not keys are rejected at compile time.
+pub trait Select {
+    /// The select keys of all values.
+    const KEYS: &'static [&'static str];
+
+    /// The select key of this value.
+    fn select_key(&self) -> &'static str;
+
+    /// Returns the value with the given key, for messages looked up at
+    /// runtime with [`MessageRef`](crate::MessageRef).
+    ///
+    /// The default implementation returns `None`, so such messages can only
+    /// be formatted with a statically typed argument.
+    fn from_select_key(key: &str) -> Option<&'static Self>
+    where
+        Self: Sized,
+    {
+        let _ = key;
+        None
+    }
+}
+
+impl<T: Select + ?Sized> Select for &T {
+    const KEYS: &'static [&'static str] = T::KEYS;
+
+    fn select_key(&self) -> &'static str {
+        (**self).select_key()
+    }
+}
+
+const fn str_eq(a: &str, b: &str) -> bool {
+    let (a, b) = (a.as_bytes(), b.as_bytes());
+    if a.len() != b.len() {
+        return false;
+    }
+    let mut i = 0;
+    while i < a.len() {
+        if a[i] != b[i] {
+            return false;
+        }
+        i += 1;
+    }
+    true
+}
+
+const fn contains(keys: &[&str], key: &str) -> bool {
+    let mut i = 0;
+    while i < keys.len() {
+        if str_eq(keys[i], key) {
+            return true;
+        }
+        i += 1;
+    }
+    false
+}
+
+/// Whether `key` is a key of `T`.
+#[doc(hidden)]
+pub const fn select_has_key<T: Select + ?Sized>(key: &str) -> bool {
+    contains(T::KEYS, key)
+}
+
+/// Whether every key of `T` is one of `cases`.
+#[doc(hidden)]
+pub const fn select_covers<T: Select + ?Sized>(cases: &[&str]) -> bool {
+    let mut i = 0;
+    while i < T::KEYS.len() {
+        if !contains(cases, T::KEYS[i]) {
+            return false;
+        }
+        i += 1;
+    }
+    true
+}
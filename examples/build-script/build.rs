@@ -0,0 +1,3 @@
+fn main() {
+    mf1_build::Builder::new().build().unwrap();
+}
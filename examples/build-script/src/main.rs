@@ -0,0 +1,29 @@
+include!(concat!(env!("OUT_DIR"), "/locales.rs"));
+
+fn main() {
+    for locale in Locale::iter() {
+        let strings = locale.get_strings();
+        println!(
+            "{}: {} {}",
+            locale.as_str(),
+            strings.welcome,
+            strings.menu.quit
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use expect_test::expect;
+    use mf1::BuildStr;
+
+    use super::Locale;
+
+    #[test]
+    fn generated_messages() {
+        let strings = Locale::es.get_strings();
+        expect!["¡Bienvenido!"].assert_eq(&strings.welcome.build_string());
+        expect!["Quit"].assert_eq(&strings.menu.quit.build_string());
+        expect!["3 archivos"].assert_eq(&strings.files.arg_count(&3).build_string());
+    }
+}
@@ -0,0 +1,188 @@
+//! Compares how long a large catalog takes to build, and how big the binary
+//! is, with `codegen = "structs"` and `codegen = "compact"`.
+//!
+//! The catalog has 40 locales of 3,000 keys, set with `MF1_BENCH_LOCALES`
+//! and `MF1_BENCH_KEYS`. Its keys are in sections of 100, and a tenth of its
+//! messages have an argument and a tenth a plural, all translated in every
+//! locale. Each codegen builds a crate which formats every message, once in
+//! the dev profile and once in the release profile, after building `mf1`
+//! and its dependencies. Run it with `cargo bench -p compact --bench build`.
+//!
+//! On a single core, this gives:
+//!
+//! | Codegen   | Dev build | Release build | Release binary |
+//! |-----------|-----------|---------------|----------------|
+//! | `structs` |    155.6s |        249.7s |       18.7 MiB |
+//! | `compact` |     34.0s |         45.3s |        6.1 MiB |
+
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::{Duration, Instant};
+
+/// Locales with plural rules of every kind, so that both codegens handle
+/// their plurals as they would in a real catalog.
+const LOCALES: &[&str] = &[
+    "en", "de", "fr", "es", "es-419", "it", "pt", "pt-PT", "nl", "sv", "da", "nb", "fi", "is",
+    "pl", "cs", "sk", "hu", "ro", "bg", "el", "ru", "uk", "lt", "lv", "et", "sl", "hr", "sr", "tr",
+    "ar", "he", "fa", "hi", "th", "vi", "id", "ja", "ko", "zh",
+];
+
+fn env_count(name: &str, default: usize) -> usize {
+    match std::env::var(name) {
+        Ok(value) => value
+            .parse()
+            .unwrap_or_else(|_| panic!("{} must be a number", name)),
+        Err(_) => default,
+    }
+}
+
+/// The locale file of a locale, with `keys` keys in sections of 100.
+fn locale_file(locale: &str, keys: usize) -> String {
+    let mut out = String::from("{");
+    for section in 0..keys.div_ceil(100) {
+        if section > 0 {
+            out.push(',');
+        }
+        let _ = write!(out, "\n    \"section_{}\": {{", section);
+        for i in section * 100..keys.min(section * 100 + 100) {
+            let message = match i % 10 {
+                8 => format!("Message {} in {} for {{name}}", i, locale),
+                9 => format!(
+                    "{{count, plural, one {{# item {i} in {locale}}} other {{# items {i} in {locale}}}}}"
+                ),
+                _ => format!("Message {} in {}", i, locale),
+            };
+            let comma = if i % 100 == 0 { "" } else { "," };
+            let _ = write!(out, "{}\n        \"key_{}\": {:?}", comma, i, message);
+        }
+        out.push_str("\n    }");
+    }
+    out.push_str("\n}\n");
+    out
+}
+
+/// Writes a crate with the catalog, which formats every message.
+fn write_crate(dir: &Path, codegen: &str, locales: &[&str], keys: usize) {
+    let workspace = Path::new(env!("CARGO_MANIFEST_DIR")).join("../..");
+    let mf1 = workspace.join("crates/mf1");
+    let tags: Vec<_> = locales
+        .iter()
+        .map(|locale| format!("{:?}", locale))
+        .collect();
+    let manifest = format!(
+        r#"[package]
+name = "catalog-{codegen}"
+version = "0.1.0"
+edition = "2021"
+publish = false
+
+[dependencies]
+mf1 = {{ path = {mf1:?} }}
+
+[package.metadata.mf1]
+locales = [{tags}]
+codegen = "{codegen}"
+
+[profile.release]
+strip = true
+
+[workspace]
+"#,
+        tags = tags.join(", "),
+    );
+    let main = r#"mf1::load_locales!();
+
+fn main() {
+    let mut out = String::new();
+    for locale in Locale::iter() {
+        for (_, message) in locale.get_strings().iter() {
+            message
+                .format(&[("name", mf1::Arg::from("Ada")), ("count", mf1::Arg::from(&2))])
+                .map(|message| out.push_str(&message))
+                .unwrap();
+        }
+    }
+    println!("{}", out.len());
+}
+"#;
+    write(&dir.join("Cargo.toml"), &manifest);
+    write(&dir.join("src/main.rs"), main);
+    for locale in locales {
+        write(
+            &dir.join(format!("locales/{}.json", locale)),
+            &locale_file(locale, keys),
+        );
+    }
+    // Builds with the same versions of the dependencies as the workspace.
+    std::fs::copy(workspace.join("Cargo.lock"), dir.join("Cargo.lock")).unwrap();
+}
+
+fn write(path: &Path, contents: &str) {
+    std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+    std::fs::write(path, contents).unwrap();
+}
+
+fn cargo(dir: &Path, target_dir: &Path, args: &[&str]) -> Duration {
+    let start = Instant::now();
+    let status = Command::new(env!("CARGO"))
+        .args(args)
+        .arg("--quiet")
+        .current_dir(dir)
+        .env("CARGO_TARGET_DIR", target_dir)
+        .status()
+        .unwrap();
+    assert!(status.success(), "cargo {} failed in {:?}", args[0], dir);
+    start.elapsed()
+}
+
+fn main() {
+    let locale_count = env_count("MF1_BENCH_LOCALES", LOCALES.len());
+    let keys = env_count("MF1_BENCH_KEYS", 3000);
+    assert!(
+        (1..=LOCALES.len()).contains(&locale_count),
+        "MF1_BENCH_LOCALES must be between 1 and {}",
+        LOCALES.len()
+    );
+    let locales = &LOCALES[..locale_count];
+    let root = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("build-bench");
+    let target_dir = root.join("target");
+
+    println!("{} locales of {} keys", locales.len(), keys);
+    println!("Codegen   Dev build  Release build  Release binary");
+    for codegen in ["structs", "compact"] {
+        let dir = root.join(codegen);
+        write_crate(&dir, codegen, locales, keys);
+        let package = format!("catalog-{}", codegen);
+        let mut times = Vec::new();
+        for profile in [&[][..], &["--release"][..]] {
+            cargo(
+                &dir,
+                &target_dir,
+                &[&["build", "-p", "mf1"][..], profile].concat(),
+            );
+            cargo(
+                &dir,
+                &target_dir,
+                &[&["clean", "-p", &package][..], profile].concat(),
+            );
+            times.push(cargo(
+                &dir,
+                &target_dir,
+                &[&["build"][..], profile].concat(),
+            ));
+        }
+        let binary =
+            target_dir
+                .join("release")
+                .join(format!("{}{}", package, std::env::consts::EXE_SUFFIX));
+        let size = std::fs::metadata(&binary).unwrap().len();
+        println!(
+            "{:<8}  {:>8.1}s  {:>12.1}s  {:>11.1} MiB",
+            codegen,
+            times[0].as_secs_f64(),
+            times[1].as_secs_f64(),
+            size as f64 / (1024.0 * 1024.0)
+        );
+    }
+}
@@ -0,0 +1,83 @@
+//! Compares formatting messages from the const structs and from the compact
+//! string tables.
+
+use std::fmt::Write;
+
+use compact::{compact as tables, structs};
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use mf1::{t_l_string as t, t_l_write};
+
+fn static_strings(c: &mut Criterion) {
+    let mut group = c.benchmark_group("static");
+    group.bench_function("structs", |b| {
+        b.iter(|| {
+            for locale in structs::Locale::iter() {
+                black_box(t!(black_box(locale), settings.notifications.email));
+            }
+        })
+    });
+    group.bench_function("compact", |b| {
+        b.iter(|| {
+            for locale in tables::Locale::iter() {
+                black_box(t!(black_box(locale), settings.notifications.email));
+            }
+        })
+    });
+    group.finish();
+}
+
+fn plurals(c: &mut Criterion) {
+    let mut group = c.benchmark_group("plural");
+    let mut out = String::new();
+    group.bench_function("structs", |b| {
+        b.iter(|| {
+            for locale in structs::Locale::iter() {
+                out.clear();
+                t_l_write!(out, black_box(locale), shared, name = "Ada", count = 3).unwrap();
+            }
+        })
+    });
+    group.bench_function("compact", |b| {
+        b.iter(|| {
+            for locale in tables::Locale::iter() {
+                out.clear();
+                t_l_write!(out, black_box(locale), shared, name = "Ada", count = 3).unwrap();
+            }
+        })
+    });
+    group.finish();
+}
+
+fn lookup(c: &mut Criterion) {
+    let mut group = c.benchmark_group("lookup");
+    let mut out = String::new();
+    group.bench_function("structs", |b| {
+        b.iter(|| {
+            for locale in structs::Locale::iter() {
+                for (_, message) in locale.get_strings().iter() {
+                    out.clear();
+                    if message.arg_names().is_empty() {
+                        message.write_to(&mut out, &()).unwrap();
+                    }
+                }
+            }
+        })
+    });
+    group.bench_function("compact", |b| {
+        b.iter(|| {
+            for locale in tables::Locale::iter() {
+                for (_, message) in locale.get_strings().iter() {
+                    out.clear();
+                    if message.arg_names().is_empty() {
+                        message.write_to(&mut out, &()).unwrap();
+                    }
+                }
+            }
+        })
+    });
+    group.finish();
+    let _ = write!(out, "");
+}
+
+criterion_group!(benches, static_strings, plurals, lookup);
+criterion_main!(benches);
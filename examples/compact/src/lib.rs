@@ -0,0 +1,111 @@
+//! The same catalog generated as const structs and as compact string tables,
+//! to compare the two.
+
+pub mod structs {
+    mf1::load_locales!(codegen = "structs");
+}
+
+pub mod compact {
+    mf1::load_locales!(codegen = "compact");
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{borrow::Cow, collections::BTreeMap, fmt::Write as _};
+
+    use expect_test::{expect, Expect};
+    use mf1::{t_l_display, t_l_string as t, t_l_write, Arg, ArgType};
+
+    use super::{compact, structs};
+
+    fn check(actual: Cow<'static, str>, expect: &Expect) {
+        expect.assert_eq(&actual);
+    }
+
+    #[test]
+    fn same_messages() {
+        let numbers: [&dyn mf1::Number; 7] = [&0, &1, &2, &3, &5, &22, &1.5];
+        for (compact, structs) in compact::Locale::iter().zip(structs::Locale::iter()) {
+            assert_eq!(compact.as_str(), structs.as_str());
+            for (key, info) in compact::Locale::KEYS.iter().zip(structs::Locale::KEYS) {
+                assert_eq!(key.path, info.path);
+                for number in numbers {
+                    let args: BTreeMap<_, _> = key
+                        .args
+                        .iter()
+                        .map(|arg| {
+                            let value = match arg.arg_type {
+                                ArgType::OrdinalArg => Arg::Number(number),
+                                ArgType::SelectArg => Arg::Str("she"),
+                                _ => Arg::Str("Ada"),
+                            };
+                            (arg.name, value)
+                        })
+                        .collect();
+                    let format =
+                        |message: Option<mf1::MessageRef>| message.unwrap().format(&args).unwrap();
+                    assert_eq!(
+                        format(compact.get_strings().get(key.path)),
+                        format(structs.get_strings().get(key.path)),
+                        "{} in {}",
+                        key.path,
+                        compact.as_str()
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn compact_strings() {
+        use compact::Locale;
+
+        check(t!(Locale::fr, title), &expect!["Boîte de réception"]);
+        check(t!(Locale::es_419, title), &expect!["Recibidos"]);
+        check(t!(Locale::es_419, empty), &expect!["No tienes mensajes."]);
+        check(
+            t!(Locale::fr, settings.notifications.push),
+            &expect!["Push notifications"],
+        );
+        assert_eq!(Locale::de.get_strings().actions.delete.as_str(), "Löschen");
+    }
+
+    #[test]
+    fn compact_formatters() {
+        use compact::Locale;
+
+        check(
+            t!(Locale::en, shared, name = "Ada", count = 3),
+            &expect!["Ada shared a file and 2 others with you."],
+        );
+        check(
+            t!(Locale::de, shared, name = "Ada", count = 2),
+            &expect!["Ada shared a file and 1 other with you."],
+        );
+        check(
+            t!(Locale::en, place, place = 23),
+            &expect!["You finished 23rd."],
+        );
+        check(
+            t!(Locale::de, place, place = 23),
+            &expect!["Du bist 23. geworden."],
+        );
+        check(
+            t!(Locale::es_419, actions.confirm, count = 1),
+            &expect!["¿Borrar 1 mensaje?"],
+        );
+        let language = Locale::fr.display_name();
+        check(
+            t!(Locale::fr, settings.language, language),
+            &expect!["Language: Français"],
+        );
+
+        let mut out = String::new();
+        t_l_write!(out, Locale::es, unread, count = 1).unwrap();
+        assert_eq!(out, "1 mensaje sin leer");
+        assert_eq!(
+            t_l_display!(Locale::fr, replied, pronoun = "he").to_string(),
+            "Il a répondu."
+        );
+    }
+}
@@ -1,7 +1,15 @@
-use mf1::{load_locales, t_l_string as t};
+use mf1::{load_locales, t_l_string as t, Mf1Select};
 
 load_locales!();
 
+#[derive(Mf1Select)]
+pub enum Pronoun {
+    He,
+    She,
+    #[mf1(key = "they")]
+    Them,
+}
+
 fn main() {
     dbg!(Locale::default());
     dbg!(Locale::en, Locale::en.get_strings());
@@ -12,6 +20,8 @@ fn main() {
 
 #[cfg(test)]
 use expect_test::{expect, Expect};
+#[cfg(test)]
+use mf1::{t_display, t_l_display, t_l_write, t_string, t_write};
 
 #[cfg(test)]
 fn check(actual: std::borrow::Cow<'static, str>, expect: &Expect) {
@@ -104,3 +114,380 @@ fn fallbacks_nested() {
         t!(Locale::en, nested.fallbacks)
     );
 }
+
+#[test]
+fn mangled_keys() {
+    check(t!(Locale::en, "sign-in"), &expect!["Sign in"]);
+    check(t!(Locale::es, "sign-in"), &expect!["Iniciar sesión"]);
+    check(t!(Locale::en, keywords.404), &expect!["Page not found"]);
+    check(t!(Locale::en, keywords."404"), &expect!["Page not found"]);
+    check(
+        t!(Locale::en, keywords.type, r#type = "keyword"),
+        &expect!["Type: keyword"],
+    );
+}
+
+#[test]
+fn plurals() {
+    let items = ["a"];
+    check(
+        t!(Locale::en, items, count = items.len()),
+        &expect!["1 item"],
+    );
+    check(t!(Locale::es, items, count = 3u8), &expect!["3 artículos"]);
+    check(
+        t!(Locale::en, items, count = u64::MAX),
+        &expect!["18446744073709551615 items"],
+    );
+    check(t!(Locale::en, hours, n = 1), &expect!["1 hour"]);
+    check(t!(Locale::en, hours, n = 1.5), &expect!["1.5 hours"]);
+    check(t!(Locale::en, place, n = 22), &expect!["22nd place"]);
+    check(
+        t!(Locale::en, party, guests = 2, host = "Ana"),
+        &expect!["Ana and one other"],
+    );
+    check(
+        t!(Locale::en, party, guests = 5, host = "Ana"),
+        &expect!["Ana and 4 others"],
+    );
+}
+
+#[test]
+fn display_args() {
+    let version = 2000;
+    check(
+        t!(Locale::en, interpolated_2, version),
+        &expect!["Frobnicator 2000"],
+    );
+}
+
+#[test]
+fn select_enums() {
+    check(
+        t!(Locale::en, replied, pronoun = Pronoun::She),
+        &expect!["She replied."],
+    );
+    check(
+        t!(Locale::en, replied, pronoun = Pronoun::Them),
+        &expect!["They replied."],
+    );
+    check(
+        t!(Locale::es, replied, pronoun = Pronoun::Them),
+        &expect!["Elle respondió."],
+    );
+    check(
+        t!(Locale::en, replied_as, pronoun = Pronoun::Them),
+        &expect!["They replied as they."],
+    );
+}
+
+#[test]
+fn display_and_write() {
+    use std::fmt::Write as _;
+    use std::io::Write as _;
+
+    let a = "Chalk";
+    check(
+        t_l_display!(Locale::en, multi_interpolation, a, b = "Cheese")
+            .to_string()
+            .into(),
+        &expect!["Chalk and Cheese"],
+    );
+    let message = t_l_display!(Locale::es, message);
+    check(message.to_string().into(), &expect!["¡Este es un mensaje!"]);
+
+    let mut out = String::new();
+    t_l_write!(out, Locale::en, items, count = 2).unwrap();
+    write!(out, "; ").unwrap();
+    t_l_write!(&mut out, Locale::en, nested.keys).unwrap();
+    check(out.into(), &expect!["2 items; You can nest keys!"]);
+
+    let mut bytes = Vec::new();
+    t_l_write!(bytes, Locale::en, replied, pronoun = Pronoun::He).unwrap();
+    assert_eq!(bytes, b"He replied.");
+}
+
+#[test]
+fn formattable_sinks() {
+    use mf1::{IoWriter, Message};
+
+    let mut out = String::new();
+    t_l_display!(Locale::en, items, count = 1)
+        .write_to(&mut out)
+        .unwrap();
+    t_l_display!(Locale::en, message)
+        .write_to(&mut out)
+        .unwrap();
+    check(out.into(), &expect!["1 itemThis is a message!"]);
+
+    let mut out = IoWriter::new(Vec::new());
+    out.write_message(&t_l_display!(Locale::es, interpolated_2, version = 3))
+        .unwrap();
+    assert_eq!(out.into_inner(), "Frobnicador 3".as_bytes());
+
+    struct Full;
+    impl std::io::Write for Full {
+        fn write(&mut self, _: &[u8]) -> std::io::Result<usize> {
+            Err(std::io::ErrorKind::StorageFull.into())
+        }
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+    let err = IoWriter::new(Full)
+        .write_message(&t_l_display!(Locale::en, items, count = 2))
+        .unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::StorageFull);
+}
+
+#[test]
+fn current_locale() {
+    use std::fmt::Write as _;
+
+    check(t_string!(message), &expect!["This is a message!"]);
+    check(
+        Locale::es.scope(|| t_string!(items, count = 2)),
+        &expect!["2 artículos"],
+    );
+
+    let guard = Locale::es.enter();
+    assert_eq!(Locale::current(), Locale::es);
+    check(
+        t_display!(interpolated_2, version = 3).to_string().into(),
+        &expect!["Frobnicador 3"],
+    );
+    let mut out = String::new();
+    Locale::en.scope(|| t_write!(out, message)).unwrap();
+    check(out.into(), &expect!["This is a message!"]);
+    drop(guard);
+    assert_eq!(Locale::current(), Locale::en);
+
+    // The explicit form ignores the current locale.
+    Locale::es.set_current();
+    check(t!(Locale::en, message), &expect!["This is a message!"]);
+    std::thread::spawn(|| assert_eq!(Locale::current(), Locale::en))
+        .join()
+        .unwrap();
+}
+
+#[test]
+fn runtime_lookup() {
+    use mf1::{Arg, FormatError};
+    use std::collections::HashMap;
+
+    let strings = Locale::es.get_strings();
+    let message = strings.get("nested.keys").unwrap();
+    assert_eq!(message.arg_names(), &[] as &[&str]);
+    check(
+        message.format(&()).unwrap().into(),
+        &expect!["¡Puedes anidar llaves!"],
+    );
+
+    let mut args = HashMap::new();
+    args.insert("count", Arg::from(&2));
+    check(
+        strings.get("items").unwrap().format(&args).unwrap().into(),
+        &expect!["2 artículos"],
+    );
+    let args = [("pronoun", Arg::select(&Pronoun::He))];
+    check(
+        strings
+            .get("replied")
+            .unwrap()
+            .format(&args)
+            .unwrap()
+            .into(),
+        &expect!["Él respondió."],
+    );
+    check(
+        strings
+            .get("replied_as")
+            .unwrap()
+            .format(&args)
+            .unwrap()
+            .into(),
+        &expect!["He replied as he."],
+    );
+    check(
+        strings
+            .get("keywords.type")
+            .unwrap()
+            .format(&[("type", Arg::Display(&'A'))])
+            .unwrap()
+            .into(),
+        &expect!["Type: A"],
+    );
+
+    assert!(strings.get("nested").is_none());
+    assert!(strings.get("nested.missing").is_none());
+    let party = strings.get("party").unwrap();
+    assert_eq!(party.arg_names(), ["guests", "host"]);
+    assert_eq!(
+        party.format(&[("guests", Arg::from(&3))]),
+        Err(FormatError::MissingArg("host"))
+    );
+    assert_eq!(
+        party.format(&[("guests", Arg::from("3")), ("host", Arg::from("Ana"))]),
+        Err(FormatError::WrongArgType {
+            arg: "guests",
+            expected: "a number"
+        })
+    );
+    assert_eq!(
+        strings
+            .get("replied")
+            .unwrap()
+            .format(&[("pronoun", Arg::from("it"))]),
+        Err(FormatError::UnknownSelectKey { arg: "pronoun" })
+    );
+
+    let paths: Vec<_> = Locale::en
+        .get_strings()
+        .iter()
+        .map(|(path, _)| path)
+        .collect();
+    check(
+        paths.join(" ").into(),
+        &expect!["hours interpolated interpolated_2 items message message_2 multi_interpolation party place replied replied_as select sign-in account.sign-out account.profile.greeting account.profile.title keywords.404 keywords.type nested.brand nested.fallbacks nested.keys"],
+    );
+}
+
+#[test]
+fn reflection() {
+    use mf1::{ArgInfo, ArgType};
+
+    let replied = Locale::KEYS.iter().find(|k| k.path == "replied").unwrap();
+    assert_eq!(
+        replied.args,
+        [ArgInfo {
+            name: "pronoun",
+            arg_type: ArgType::SelectArg,
+            select_type: Some("crate::Pronoun"),
+        }]
+    );
+    assert_eq!(replied.translated, [Locale::en, Locale::es]);
+    assert_eq!(replied.fallbacks, [Locale::es_419]);
+
+    let brand = Locale::KEYS
+        .iter()
+        .find(|k| k.path == "nested.brand")
+        .unwrap();
+    assert!(brand.args.is_empty());
+    assert!(brand.is_translated(Locale::en));
+    assert!(!brand.is_translated(Locale::es));
+    assert_eq!(brand.fallbacks, [Locale::es, Locale::es_419]);
+
+    let party = Locale::KEYS.iter().find(|k| k.path == "party").unwrap();
+    let args: Vec<_> = party.args.iter().map(|a| (a.name, a.arg_type)).collect();
+    assert_eq!(
+        args,
+        [("guests", ArgType::OrdinalArg), ("host", ArgType::PlainArg)]
+    );
+
+    for locale in Locale::VALUES {
+        for key in Locale::KEYS {
+            let message = locale.get_strings().get(key.path).unwrap();
+            let names: Vec<_> = key.args.iter().map(|a| a.name).collect();
+            assert_eq!(message.arg_names(), names, "{}", key.path);
+        }
+    }
+    let paths: Vec<_> = Locale::KEYS.iter().map(|k| k.path).collect();
+    check(
+        paths.join(" ").into(),
+        &expect!["account.profile.greeting account.profile.title account.sign-out hours interpolated interpolated_2 items keywords.404 keywords.type message message_2 multi_interpolation nested.brand nested.fallbacks nested.keys party place replied replied_as select sign-in"],
+    );
+}
+
+#[test]
+fn locale_helpers() {
+    use mf1::{icu_locid::LanguageIdentifier, TextDirection};
+
+    assert_eq!(Locale::iter().collect::<Vec<_>>(), Locale::VALUES);
+    let names: Vec<_> = Locale::iter().map(Locale::display_name).collect();
+    assert_eq!(names, ["English", "Español", "Español (Latinoamérica)"]);
+    assert_eq!(Locale::es.text_direction(), TextDirection::LeftToRight);
+    assert_eq!(Locale::es.text_direction().as_html_dir(), "ltr");
+    assert_eq!(
+        LanguageIdentifier::from(Locale::es),
+        "es".parse::<LanguageIdentifier>().unwrap()
+    );
+
+    assert_eq!(Locale::try_from(" es "), Ok(Locale::es));
+    assert_eq!("EN".parse(), Ok(Locale::en));
+    let err = "fr".parse::<Locale>().unwrap_err();
+    assert_eq!(err.locale(), "fr");
+    check(
+        err.to_string().into(),
+        &expect![[r#"Unknown locale "fr", expected one of: en, es, es-419"#]],
+    );
+}
+
+#[test]
+fn serde() {
+    assert_eq!(serde_json::to_string(&Locale::es).unwrap(), r#""es""#);
+    let locales: Vec<Locale> = serde_json::from_str(r#"["en", " es"]"#).unwrap();
+    assert_eq!(locales, [Locale::en, Locale::es]);
+    let err = serde_json::from_str::<Locale>(r#""fr""#).unwrap_err();
+    check(
+        err.to_string().into(),
+        &expect![[
+            r#"invalid value: string "fr", expected one of the locales en, es, es-419 at line 1 column 4"#
+        ]],
+    );
+}
+
+#[test]
+#[cfg(all(feature = "locale-es", feature = "locale-es-419"))]
+fn fallback_chains() {
+    // Translated in es-419.
+    check(t!(Locale::es_419, "sign-in"), &expect!["Ingresar"]);
+    // Missing in es-419, so taken from es.
+    check(
+        t!(Locale::es_419, items, count = 2),
+        &expect!["2 artículos"],
+    );
+    // Missing in es-419 and es, so taken from en.
+    check(
+        t!(Locale::es_419, message_2),
+        &expect!["This is a second message!"],
+    );
+}
+
+#[test]
+fn locale_features() {
+    // Run with `--no-default-features --features locale-es` to check that
+    // es-419 then uses the messages of es, and is listed as a fallback.
+    assert!(Locale::en.is_enabled());
+    assert_eq!(Locale::es.is_enabled(), cfg!(feature = "locale-es"));
+    let sign_in = if cfg!(feature = "locale-es-419") {
+        "Ingresar"
+    } else if cfg!(feature = "locale-es") {
+        "Iniciar sesión"
+    } else {
+        "Sign in"
+    };
+    assert_eq!(t!(Locale::es_419, "sign-in"), sign_in);
+
+    // A locale which is compiled out takes every message from its fallback.
+    let key = Locale::KEYS.iter().find(|k| k.path == "sign-in").unwrap();
+    for (locale, enabled) in [
+        (Locale::es, cfg!(feature = "locale-es")),
+        (Locale::es_419, cfg!(feature = "locale-es-419")),
+    ] {
+        assert_eq!(key.is_translated(locale), enabled);
+        assert_eq!(key.fallbacks.contains(&locale), !enabled);
+    }
+}
+
+#[test]
+fn namespaces() {
+    // From locales/es/account/profile.json.
+    check(t!(Locale::es, account.profile.title), &expect!["Tu perfil"]);
+    // From locales/en/account.json, which es doesn't have.
+    check(t!(Locale::es, account."sign-out"), &expect!["Sign out"]);
+    let name = "Ada";
+    check(
+        t!(Locale::en, account.profile.greeting, name),
+        &expect!["Hi, Ada!"],
+    );
+}